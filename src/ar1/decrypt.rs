@@ -0,0 +1,25 @@
+use crate::ar1::seeds;
+use crate::ar2;
+use crate::cheat::Cheat;
+use crate::error::{Error, ErrorKind, Result};
+
+// Original source: ar1.c:ar1BatchDecrypt()
+// Decrypt every address/value pair of an AR1 cheat with the fixed AR1 seeds
+pub fn decrypt_cheat(input: Cheat) -> Result<Cheat> {
+    if !input.codes.len().is_multiple_of(2) {
+        return Err(Error::new(ErrorKind::MissingValue).in_cheat(&input).at_line(input.codes.len() / 2));
+    }
+    let seeds = seeds();
+
+    // Clone input to update and return
+    let mut output = input.clone();
+
+    // Decrypt each pair
+    for i in (0..output.codes.len()).step_by(2) {
+        let line = |e: Error| e.in_cheat(&input).at_line(i / 2);
+        output.codes[i] = ar2::decrypt::decrypt_code(output.codes[i], seeds[0], seeds[1]).map_err(line)?;
+        output.codes[i+1] = ar2::decrypt::decrypt_code(output.codes[i+1], seeds[2], seeds[3]).map_err(line)?;
+    }
+
+    Ok(output)
+}
@@ -0,0 +1,12 @@
+pub mod decrypt;
+pub mod encrypt;
+
+use crate::ar2;
+
+// Fixed AR1 seed key. AR1 devices don't support DEADFACE key changes.
+const SEED_KEY: u32 = 0x05100518;
+
+// Generate AR1 seeds
+pub fn seeds() -> [u8; 4] {
+    ar2::seeds::regenerate(SEED_KEY)
+}
@@ -1,47 +1,57 @@
 use crate::magic;
-use crate::ar2::flip_nibble;
+use crate::ar2::{check_seed, flip_nibble, KEY_ADDR};
 use crate::ar2::seeds;
 use crate::ar2::table;
+use crate::error::{Error, ErrorKind, Result};
+use crate::game::Game;
 
 // Decrypt a list of AR2 codes stored as address/value octet pairs
-pub fn decrypt_cheat(input: Vec<u32>, seeds: &[u8; 4]) -> Vec<u32> {
-    // Clone given AR2 seeds to manipulate
-    let mut seeds = seeds.clone();
-
-    // Clone given input to manipulate and return
-    let mut output = input.clone();
+// Remarks: DEADFACE key lines change the given seeds for all following lines, and are dropped from the output
+pub fn decrypt_cheat(input: Vec<u32>, seeds: &mut [u8; 4]) -> Result<Vec<u32>> {
+    if !input.len().is_multiple_of(2) {
+        return Err(Error::new(ErrorKind::MissingValue).at_line(input.len() / 2));
+    }
 
-    // Get length of input for use in iterations
-    let mut size = output.len();
+    // Output codes
+    let mut output: Vec<u32> = vec!();
 
-    // TODO: Replace custom pair iterators with (x..y).step_by(z)
     // Decrypt AR2 codes
-    for mut i in (0..size).step_by(2) {
+    for (line, pair) in input.chunks_exact(2).enumerate() {
         // Decrypt address and value octets
-        output[i] = decrypt_code(output[i], seeds[0], seeds[1]);
-        output[i+1] = decrypt_code(output[i+1], seeds[2], seeds[3]);
+        let addr = decrypt_code(pair[0], seeds[0], seeds[1]).map_err(|e| e.at_line(line))?;
+        let val = decrypt_code(pair[1], seeds[2], seeds[3]).map_err(|e| e.at_line(line))?;
 
-        // TODO: What does the 0xDEADFACE string represent for AR2?
-        // Check if address is special address
-        if output[i] == 0xDEADFACE {
+        // Check if address is the key address
+        if addr == KEY_ADDR {
             // Generate new seeds based off the value
-            seeds = seeds::regenerate(output[i+1]);
-
-            if (i+2) < size {
-                for j in (i+2)..size {
-                    output[j-2] = output[j];
-                }
-            }
-            i -= 2;
-            size -= 2;
+            *seeds = seeds::regenerate(val);
+            continue;
         }
+
+        output.push(addr);
+        output.push(val);
+    }
+
+    Ok(output)
+}
+
+// Decrypt every cheat of a game, carrying seed changes over from one cheat to the next
+pub fn decrypt_game(input: Game, seeds: &[u8; 4]) -> Result<Game> {
+    // Copy given AR2 seeds to manipulate
+    let mut seeds = *seeds;
+
+    // Clone input to update and return
+    let mut output = input.clone();
+
+    for cheat in output.cheats.iter_mut() {
+        cheat.codes = decrypt_cheat(cheat.codes.clone(), &mut seeds).map_err(|e| e.in_cheat(cheat))?;
     }
 
-    output
+    Ok(output)
 }
 
 // Decrypt a single AR2 octet stored as u32
-pub fn decrypt_code(input: u32, in_ctrl: u8, seed: u8) -> u32 {
+pub fn decrypt_code(input: u32, in_ctrl: u8, seed: u8) -> Result<u32> {
 
     // Handle control value (original source: type)
     let mut ctrl = in_ctrl;
@@ -50,9 +60,10 @@ pub fn decrypt_code(input: u32, in_ctrl: u8, seed: u8) -> u32 {
             ctrl = 1;
         }
         else {
-            return magic::invert(input);
+            return Ok(magic::invert(input));
         }
     }
+    check_seed(seed)?;
 
     // Break input up into byte array
     let mut output: [u8; 4] = [
@@ -63,7 +74,7 @@ pub fn decrypt_code(input: u32, in_ctrl: u8, seed: u8) -> u32 {
 
     ];
 
-    println!("{:?}", output);
+    log::trace!("AR2 bytes before decryption: {:02X?}", output);
 
     match ctrl {
         0 => {
@@ -85,10 +96,10 @@ pub fn decrypt_code(input: u32, in_ctrl: u8, seed: u8) -> u32 {
             output[0] = magic::add_u8_overflow(output[0], table::T3[seed as usize]);
         },
         3 => {
-            output[3] -= table::T3[seed as usize];
-            output[2] -= table::T2[seed as usize];
-            output[1] -= table::T1[seed as usize];
-            output[0] -= table::T0[seed as usize];
+            output[3] = output[3].wrapping_sub(table::T3[seed as usize]);
+            output[2] = output[2].wrapping_sub(table::T2[seed as usize]);
+            output[1] = output[1].wrapping_sub(table::T1[seed as usize]);
+            output[0] = output[0].wrapping_sub(table::T0[seed as usize]);
         },
         4 => {
             output[3] = magic::add_u8_overflow(output[3] ^ table::T0[seed as usize], table::T0[seed as usize]);
@@ -97,26 +108,25 @@ pub fn decrypt_code(input: u32, in_ctrl: u8, seed: u8) -> u32 {
             output[0] = magic::add_u8_overflow(output[0] ^ table::T2[seed as usize], table::T2[seed as usize]);
         },
         5 => {
-            output[3] = (output[3] - table::T1[seed as usize]) ^ table::T0[seed as usize];
-            output[2] = (output[2] - table::T2[seed as usize]) ^ table::T1[seed as usize];
-            output[1] = (output[1] - table::T3[seed as usize]) ^ table::T2[seed as usize];
-            output[0] = (output[0] - table::T0[seed as usize]) ^ table::T3[seed as usize];
+            output[3] = output[3].wrapping_sub(table::T1[seed as usize]) ^ table::T0[seed as usize];
+            output[2] = output[2].wrapping_sub(table::T2[seed as usize]) ^ table::T1[seed as usize];
+            output[1] = output[1].wrapping_sub(table::T3[seed as usize]) ^ table::T2[seed as usize];
+            output[0] = output[0].wrapping_sub(table::T0[seed as usize]) ^ table::T3[seed as usize];
         },
         6 => {
-            output[3] += table::T0[seed as usize];
-            output[2] -= table::T1[((seed + 1) & 31) as usize];
-            output[1] += table::T2[((seed + 2) & 31) as usize];
-            output[0] -= table::T3[((seed + 3) & 31) as usize];
+            output[3] = output[3].wrapping_add(table::T0[seed as usize]);
+            output[2] = output[2].wrapping_sub(table::T1[((seed + 1) & 31) as usize]);
+            output[1] = output[1].wrapping_add(table::T2[((seed + 2) & 31) as usize]);
+            output[0] = output[0].wrapping_sub(table::T3[((seed + 3) & 31) as usize]);
         }
         c => {
-            // TODO: Handle AR2 parsing errors more gracefully
-            panic!("[!] Received unrecognized control value in ar2/decrypt: {}", c);
+            return Err(Error::new(ErrorKind::ControlValue(c)));
         }
     }
 
     // Reconstruct output into u32
-    ((output[3] as u32) << 24) as u32 +
+    Ok(((output[3] as u32) << 24) as u32 +
         ((output[2] as u32) << 16) as u32 +
         ((output[1] as u32) << 8) as u32 +
-        output[0] as u32
+        output[0] as u32)
 }
\ No newline at end of file
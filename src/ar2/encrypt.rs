@@ -0,0 +1,124 @@
+use crate::magic;
+use crate::ar2::{check_seed, flip_nibble, KEY_ADDR};
+use crate::ar2::seeds;
+use crate::ar2::table;
+use crate::error::{Error, ErrorKind, Result};
+use crate::game::Game;
+
+// Encrypt a list of AR2 codes stored as address/value octet pairs
+// Remarks: DEADFACE lines in the input are encrypted with the given seeds, then change them for all following lines
+pub fn encrypt_cheat(input: Vec<u32>, seeds: &mut [u8; 4]) -> Result<Vec<u32>> {
+    if !input.len().is_multiple_of(2) {
+        return Err(Error::new(ErrorKind::MissingValue).at_line(input.len() / 2));
+    }
+
+    // Clone given input to manipulate and return
+    let mut output = input.clone();
+
+    for i in (0..output.len()).step_by(2) {
+        // Encrypt address and value octets
+        output[i] = encrypt_code(input[i], seeds[0], seeds[1]).map_err(|e| e.at_line(i / 2))?;
+        output[i+1] = encrypt_code(input[i+1], seeds[2], seeds[3]).map_err(|e| e.at_line(i / 2))?;
+
+        // Switch seeds after a key line
+        if input[i] == KEY_ADDR {
+            *seeds = seeds::regenerate(input[i+1]);
+        }
+    }
+
+    Ok(output)
+}
+
+// Encrypt a list of AR2 codes, preceded by a DEADFACE line switching to the seeds for a chosen key
+pub fn encrypt_cheat_rekeyed(input: Vec<u32>, seeds: &mut [u8; 4], key: u32) -> Result<Vec<u32>> {
+    let mut codes = vec!(KEY_ADDR, key);
+    codes.extend(input);
+
+    encrypt_cheat(codes, seeds)
+}
+
+// Encrypt every cheat of a game, carrying seed changes over from one cheat to the next
+pub fn encrypt_game(input: Game, seeds: &[u8; 4]) -> Result<Game> {
+    // Copy given AR2 seeds to manipulate
+    let mut seeds = *seeds;
+
+    // Clone input to update and return
+    let mut output = input.clone();
+
+    for cheat in output.cheats.iter_mut() {
+        cheat.codes = encrypt_cheat(cheat.codes.clone(), &mut seeds).map_err(|e| e.in_cheat(cheat))?;
+    }
+
+    Ok(output)
+}
+
+// Encrypt a single AR2 octet stored as u32. Exact inverse of decrypt::decrypt_code().
+pub fn encrypt_code(input: u32, in_ctrl: u8, seed: u8) -> Result<u32> {
+
+    // Handle control value (original source: type)
+    let mut ctrl = in_ctrl;
+    if ctrl == 7 {
+        if seed & 1 > 0 {
+            ctrl = 1;
+        }
+        else {
+            return Ok(magic::invert(input));
+        }
+    }
+    check_seed(seed)?;
+
+    // Break input up into byte array
+    let mut output: [u8; 4] = input.to_le_bytes();
+
+    let s = seed as usize;
+    match ctrl {
+        0 => {
+            output[3] ^= table::T0[s];
+            output[2] ^= table::T1[s];
+            output[1] ^= table::T2[s];
+            output[0] ^= table::T3[s];
+        },
+        1 => {
+            output[3] = flip_nibble(output[3] ^ table::T0[s]);
+            output[2] = flip_nibble(output[2] ^ table::T2[s]);
+            output[1] = flip_nibble(output[1] ^ table::T3[s]);
+            output[0] = flip_nibble(output[0] ^ table::T1[s]);
+        },
+        2 => {
+            output[3] = output[3].wrapping_sub(table::T0[s]);
+            output[2] = output[2].wrapping_sub(table::T1[s]);
+            output[1] = output[1].wrapping_sub(table::T2[s]);
+            output[0] = output[0].wrapping_sub(table::T3[s]);
+        },
+        3 => {
+            output[3] = output[3].wrapping_add(table::T3[s]);
+            output[2] = output[2].wrapping_add(table::T2[s]);
+            output[1] = output[1].wrapping_add(table::T1[s]);
+            output[0] = output[0].wrapping_add(table::T0[s]);
+        },
+        4 => {
+            output[3] = output[3].wrapping_sub(table::T0[s]) ^ table::T0[s];
+            output[2] = output[2].wrapping_sub(table::T3[s]) ^ table::T3[s];
+            output[1] = output[1].wrapping_sub(table::T1[s]) ^ table::T1[s];
+            output[0] = output[0].wrapping_sub(table::T2[s]) ^ table::T2[s];
+        },
+        5 => {
+            output[3] = (output[3] ^ table::T0[s]).wrapping_add(table::T1[s]);
+            output[2] = (output[2] ^ table::T1[s]).wrapping_add(table::T2[s]);
+            output[1] = (output[1] ^ table::T2[s]).wrapping_add(table::T3[s]);
+            output[0] = (output[0] ^ table::T3[s]).wrapping_add(table::T0[s]);
+        },
+        6 => {
+            output[3] = output[3].wrapping_sub(table::T0[s]);
+            output[2] = output[2].wrapping_add(table::T1[(s + 1) & 31]);
+            output[1] = output[1].wrapping_sub(table::T2[(s + 2) & 31]);
+            output[0] = output[0].wrapping_add(table::T3[(s + 3) & 31]);
+        }
+        c => {
+            return Err(Error::new(ErrorKind::ControlValue(c)));
+        }
+    }
+
+    // Reconstruct output into u32
+    Ok(u32::from_le_bytes(output))
+}
@@ -1,12 +1,26 @@
 mod table;
 pub mod decrypt;
+pub mod encrypt;
 pub mod seeds;
 
+use crate::error::{Error, ErrorKind, Result};
+
+// Address of the code line changing AR2 seeds. Its value is the new seed key.
+pub const KEY_ADDR: u32 = 0xDEADFACE;
+
 // Original source: ar2.c:nibble_flip()
 pub fn flip_nibble(input: u8) -> u8 {
     (input << 4) | (input >> 4)
 }
 
+// Make sure a seed can index the seed tables. Seeds from DEADFACE key lines may not.
+pub fn check_seed(seed: u8) -> Result<()> {
+    if seed as usize >= table::T0.len() {
+        return Err(Error::new(ErrorKind::SeedValue(seed)));
+    }
+    Ok(())
+}
+
 // TODO: De-duplicate common operations
 // Original source: common.c:swapbytes()
 pub fn swap_bytes(input: u32) -> u32 {
@@ -1,12 +1,14 @@
 use crate::armax::table;
-use crate::armax::{rotate_left, rotate_right, swap_bytes};
+use crate::armax::verifier::EXPANSION_SIZES;
+use crate::armax::{crc_nibble, rotate_left, rotate_right, swap_bytes};
 use crate::ar2;
 use crate::cheat::Cheat;
+use crate::error::{Error, ErrorKind, Result};
 use crate::magic;
 
 // TODO: Translate alpha_to_octets() from alphatobin() less literally
 // Decode ARMAX lines into pairs of address/value octets
-pub fn alpha_to_octets(input: Vec<&str>) -> Option<Vec<(u32, u32)>> {
+pub fn alpha_to_octets(input: Vec<&str>) -> Result<Vec<(u32, u32)>> {
     // Output octets
     let mut output: Vec<(u32, u32)> = vec!();
 
@@ -26,24 +28,24 @@ pub fn alpha_to_octets(input: Vec<&str>) -> Option<Vec<(u32, u32)>> {
     // Read input lines
     while lines_left > 0 {
 
-        // TODO: Swap panics for printlns
         // TODO: De-duplicate octet building
 
+        // Index of the line being read
+        let line = octet_count>>1;
+
         // Build 1st octet (code address)
         let mut octet1: u32 = 0;
         for index in 0..7 {
             // Get input char at this index
-            match input[octet_count>>1].chars().nth(index) {
+            match input[line].chars().nth(index) {
                 None => {
-                    // TODO: Handle parsing errors gracefully
-                    panic!("[!] Unable to get character at {} from input of length {}", index, input[octet_count>>1].len());
+                    return Err(Error::new(ErrorKind::LineTooShort { length: input[line].len() }).at_line(line));
                 }
                 Some(char_in) => {
                     // Get index of input char in cipher alphabet
                     match alphabet.iter().position(|&char_alpha| char_alpha == char_in) {
                         None => {
-                            // TODO: Handle parsing error gracefully
-                            panic!("[!] Received non-alphanumeric character \"{}\" in ARMAX code at index {}", char_in, index);
+                            return Err(Error::new(ErrorKind::InvalidCharacter { character: char_in, index }).at_line(line));
                         }
                         Some(match_index) => {
                             // OR octet w/ index of input char in alphabet string,
@@ -68,17 +70,15 @@ pub fn alpha_to_octets(input: Vec<&str>) -> Option<Vec<(u32, u32)>> {
         let mut octet2: u32 = 0;
         for index in 0..7 {
             // Get input char at this index
-            match input[octet_count>>1].chars().nth(index+6) {
+            match input[line].chars().nth(index+6) {
                 None => {
-                    // TODO: Handle parsing errors gracefully
-                    panic!("[!] Unable to get character at {} from input of length {}", index+6, input[octet_count>>1].len());
+                    return Err(Error::new(ErrorKind::LineTooShort { length: input[line].len() }).at_line(line));
                 }
                 Some(char_in) => {
                     // Get index of input char in cipher alphabet
                     match alphabet.iter().position(|&char_alpha| char_alpha == char_in) {
                         None => {
-                            // TODO: Handle parsing error gracefully
-                            panic!("[!] Received non-alphanumeric character \"{}\" in ARMAX code at index {}", char_in, index+6);
+                            return Err(Error::new(ErrorKind::InvalidCharacter { character: char_in, index: index+6 }).at_line(line));
                         }
                         Some(match_index) => {
                             // OR octet w/ index of input char in alphabet string,
@@ -111,21 +111,19 @@ pub fn alpha_to_octets(input: Vec<&str>) -> Option<Vec<(u32, u32)>> {
         }
 
         // Verify parity bit and potentially add to output list
-        match input[(octet_count-2)>>1].chars().nth(12) {
+        match input[line].chars().nth(12) {
             None => {
-                // TODO: Handle parsing errors gracefully
-                panic!("[!] Unable to get character at 12 from input of length {}", input[octet_count>>1].len());
+                return Err(Error::new(ErrorKind::LineTooShort { length: input[line].len() }).at_line(line));
             }
             Some(char_in) => {
                 // Get index of input char in cipher alphabet
                 match alphabet.iter().position(|&char_alpha| char_alpha == char_in) {
                     None => {
-                        // TODO: Handle parsing error gracefully
-                        panic!("[!] Received non-alphanumeric character \"{}\" in ARMAX code at index {}", char_in, 12);
+                        return Err(Error::new(ErrorKind::InvalidCharacter { character: char_in, index: 12 }).at_line(line));
                     }
                     Some(match_index) => {
                         if parity&1 != ((match_index as u8)&1) {
-                            panic!("[!] Parity bit validation failed! Octets: {:08X} / {:08X}", octet1, octet2);
+                            return Err(Error::new(ErrorKind::Parity { octets: (octet1, octet2) }).at_line(line));
                         }
                         else {
                             // Parity check passed! Add octets to output list
@@ -140,7 +138,7 @@ pub fn alpha_to_octets(input: Vec<&str>) -> Option<Vec<(u32, u32)>> {
         lines_left-=1;
     }
 
-    Some(output)
+    Ok(output)
 }
 
 /*
@@ -217,7 +215,11 @@ pub fn whole_game(game: Game, input: &Vec<u32>, seeds: &[u32; 32]) -> Game {
 }*/
 
 // Equivalent to armax.c:batchdecrypt() + armax.c:armBatchDecryptFull()
-pub fn decrypt_cheat(input: Cheat, armax_seeds: &[u32; 32], ar2_seeds: &[u8; 4]) -> Cheat {
+pub fn decrypt_cheat(input: Cheat, armax_seeds: &[u32; 32], ar2_seeds: &mut [u8; 4]) -> Result<Cheat> {
+    if !input.codes.len().is_multiple_of(2) {
+        return Err(Error::new(ErrorKind::MissingValue).in_cheat(&input).at_line(input.codes.len() / 2));
+    }
+
     // Decrypt address/value pairs from pairs of u32 codes
     let mut out_codes: Vec<u32> = vec!();
 
@@ -227,23 +229,23 @@ pub fn decrypt_cheat(input: Cheat, armax_seeds: &[u32; 32], ar2_seeds: &[u8; 4])
         out_codes.push(addr);
         out_codes.push(val);
     }
-    // TODO: Check for trailing address/value?
 
     if out_codes.len() > 0 {
 
         // Read cheat metadata and update output cheat
-        let mut decrypted = read_cheat_meta(&input, &out_codes);
+        let mut decrypted = read_cheat_meta(&input, &out_codes).map_err(|e| e.in_cheat(&input).at_line(0))?;
 
-        // TODO: Verify the output codes with CRC16
+        // Verify the output codes against the CRC16 nibble stored in the 1st code
+        decrypted.crc_valid = Some(crc_nibble(&out_codes) == out_codes[0] >> 28);
 
         // Apply mask to 1st code
         out_codes[0] &= 0x0FFFFFFF;
 
         // Determine ARMAX verifier code count (given two u32 code octets per line)
-        let verifier_code_count = (read_verifier_length(&out_codes) as usize) * 2;
+        let verifier_code_count = (read_verifier_length(&out_codes).map_err(|e| e.in_cheat(&input).at_line(0))? as usize) * 2;
 
         // Determine non-ARMAX-verifier (i.e. AR2) code count
-        let ar2_code_count = out_codes.len() - verifier_code_count;
+        let ar2_code_count = out_codes.len().saturating_sub(verifier_code_count);
 
         if ar2_code_count > 0 {
 
@@ -258,8 +260,12 @@ pub fn decrypt_cheat(input: Cheat, armax_seeds: &[u32; 32], ar2_seeds: &[u8; 4])
                 ar2_codes[i] = swap_bytes(ar2_codes[i]);
             }
 
-            // Decrypt all AR2 codes
-            ar2_codes = ar2::decrypt::decrypt_cheat(ar2_codes, ar2_seeds);
+            // Decrypt all AR2 codes. Key lines change the seeds for the rest of the stream, like ar2::decrypt::decrypt_game().
+            ar2_codes = ar2::decrypt::decrypt_cheat(ar2_codes, ar2_seeds).map_err(|mut e| {
+                // Count lines from the start of the cheat, verifier included
+                e.line = e.line.map(|line| line + verifier_code_count / 2);
+                e.in_cheat(&input)
+            })?;
 
             // Re-combine decrypted ARMAX codes and newly-decrypted AR2 codes
             out_codes = max_codes.to_vec();
@@ -268,11 +274,10 @@ pub fn decrypt_cheat(input: Cheat, armax_seeds: &[u32; 32], ar2_seeds: &[u8; 4])
 
         // Return our decrypted cheat
         decrypted.codes = out_codes;
-        decrypted
+        Ok(decrypted)
     }
     else {
-        // TODO: Handle errors more elegantly
-        panic!("[!] No ARMAX cheats decrypted");
+        Err(Error::new(ErrorKind::NoCodes).in_cheat(&input))
     }
 }
 
@@ -377,7 +382,7 @@ pub fn unscramble_2(mut addr: u32, mut val: u32) -> (u32, u32) {
 }
 
 // Read metadata from decrypted codes and update provided input Cheat
-fn read_cheat_meta(input: &Cheat, codes: &Vec<u32>) -> Cheat {
+pub fn read_cheat_meta(input: &Cheat, codes: &Vec<u32>) -> Result<Cheat> {
     // Clone input to update and return
     let mut output = input.clone();
 
@@ -389,17 +394,17 @@ fn read_cheat_meta(input: &Cheat, codes: &Vec<u32>) -> Cheat {
     ];
 
     // WARNING: READING PERMUTES THE KEY ARRAY - ORDER MATTERS!
-    output.game_id = read_bit_string(&codes, &mut key, 13);
-    output.id = read_bit_string(&codes, &mut key, 19);
-    output.enable_code = read_bit_string(&codes, &mut key, 1) == 1;
-    let _unknown= read_bit_string(&codes, &mut key, 1) == 1;
-    output.region = read_bit_string(&codes, &mut key, 2) as u8;
-    output
+    output.game_id = read_bit_string(&codes, &mut key, 13)?;
+    output.id = read_bit_string(&codes, &mut key, 19)?;
+    output.enable_code = read_bit_string(&codes, &mut key, 1)? == 1;
+    let _unknown= read_bit_string(&codes, &mut key, 1)? == 1;
+    output.region = read_bit_string(&codes, &mut key, 2)? as u8;
+    Ok(output)
 }
 
 // Original source: armax.c:armReadVerifier()
 // Read verifier bit string from a decrypted cheat and return the number of code lines it occupies
-fn read_verifier_length(input: &Vec<u32>) -> i16 {
+pub fn read_verifier_length(input: &Vec<u32>) -> Result<i16> {
     // TODO: [oddity] Is lines=1 an off-by-one in the original code? It's definitely required.
     // Output line count
     let mut lines: i16 = 1;
@@ -407,18 +412,6 @@ fn read_verifier_length(input: &Vec<u32>) -> i16 {
     // Bit counter
     let mut bits_read = 0;
 
-    // Expansion sizes
-    let exp_sizes: [u8; 8] = [
-        6,      // ?
-        10,     // ?
-        12,     // ?
-        19,     // Folder content
-        19,     // Folder content
-        8,      // Folder
-        7,      // ?
-        32,     // Disc hashes, other?
-    ];
-
     // Key array for bit string operations
     let mut key: [u32; 3] = [
         1u32,   // Skip reading first WORD
@@ -427,20 +420,20 @@ fn read_verifier_length(input: &Vec<u32>) -> i16 {
     ];
 
     // Get initial verifier terminator
-    let mut terminator = read_bit_string(input, &mut key, 1);   // TODO: Return an error if this errors
+    let mut terminator = read_bit_string(input, &mut key, 1)?;
     bits_read += 1;
 
     while terminator < 1 {
         // Get index into expansion size array
-        let exp_index = read_bit_string(input, &mut key, 3) as usize;
+        let exp_index = read_bit_string(input, &mut key, 3)? as usize;
         bits_read += 3;
 
         // Get expansion data (unused)
-        let _ = read_bit_string(input, &mut key, exp_sizes[exp_index]);
-        bits_read += exp_sizes[exp_index];
+        let _ = read_bit_string(input, &mut key, EXPANSION_SIZES[exp_index])?;
+        bits_read += EXPANSION_SIZES[exp_index];
 
         // Get next verifier terminator
-        terminator = read_bit_string(input, &mut key, 1);
+        terminator = read_bit_string(input, &mut key, 1)?;
         bits_read += 1;
     }
 
@@ -456,28 +449,26 @@ fn read_verifier_length(input: &Vec<u32>) -> i16 {
         }
     }
 
-    lines
+    Ok(lines)
 }
 
 // Read bits from arbitrary indexes within a Vec<u32> to form a u32
-fn read_bit_string(input: &Vec<u32>, ctrl: &mut [u32; 3], length: u8) -> u32 {
+fn read_bit_string(input: &Vec<u32>, ctrl: &mut [u32; 3], length: u8) -> Result<u32> {
 
     let mut output: u32 = 0;
-    let mut tmp: u32 = magic::u32_pointer_increment(input, ctrl[0] << 2);
 
     for _ in 0..length {
         if ctrl[1] > 31 {
             ctrl[1] = 0;
             ctrl[0] += 1;
-            tmp = magic::u32_pointer_increment(input, ctrl[0] << 2);
         }
-        if ctrl[0] >= ctrl[2] {
-            // TODO: Allow indicating error here instead of just panicking
-            panic!("Error getting bitstring of length {}", length);
+        if ctrl[0] >= ctrl[2] || ctrl[0] as usize >= input.len() {
+            return Err(Error::new(ErrorKind::BitString { length }));
         }
+        let tmp: u32 = magic::u32_pointer_increment(input, ctrl[0] << 2);
         output = (output << 1) | ((tmp >> (31 - ctrl[1])) & 1);
         ctrl[1] += 1;
     }
 
-    output
+    Ok(output)
 }
\ No newline at end of file
@@ -0,0 +1,196 @@
+use crate::armax::decrypt::{octet_mask, read_verifier_length};
+use crate::armax::{crc_nibble, rotate_left, rotate_right, swap_bytes};
+use crate::ar2;
+use crate::cheat::Cheat;
+use crate::error::{Error, ErrorKind, Result};
+
+// Cipher alphabet used by ARMAX lines
+const ALPHABET: [char; 32] = [
+    '0', '1', '2', '3', '4', '5', '6', '7',
+    '8', '9', 'A', 'B', 'C', 'D', 'E', 'F',
+    'G', 'H', 'J', 'K', 'M', 'N', 'P', 'Q',
+    'R', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z'
+];
+
+// Original source: armax.c:bintoalpha()
+// Encode pairs of address/value octets into "XXXX-XXXX-XXXXX" ARMAX lines
+pub fn octets_to_alpha(input: &[(u32, u32)]) -> Vec<String> {
+    // Output lines
+    let mut output: Vec<String> = vec!();
+
+    for &(octet1, octet2) in input {
+        // Indexes into the cipher alphabet for each of the 13 characters
+        let mut indexes = [0u32; 13];
+
+        // Characters 0-5 hold the upper 30 bits of the 1st octet (code address)
+        for (index, alpha) in indexes.iter_mut().enumerate().take(6) {
+            *alpha = (octet1 >> (((5-index)*5)+2)) & 0x1F;
+        }
+        // Character 6 straddles both octets
+        indexes[6] = ((octet1 & 0x03) << 3) | (octet2 >> 29);
+        // Characters 7-11 hold the middle 25 bits of the 2nd octet (code value)
+        for (index, alpha) in indexes.iter_mut().enumerate().skip(7).take(5) {
+            *alpha = (octet2 >> (((11-index)*5)+4)) & 0x1F;
+        }
+        // Character 12 holds the last 4 bits of the 2nd octet followed by the parity bit
+        indexes[12] = ((octet2 & 0x0F) << 1) | parity(octet1, octet2);
+
+        // Build the line, adding dashes after the 4th and 8th characters
+        let mut line = String::with_capacity(15);
+        for (i, index) in indexes.iter().enumerate() {
+            if i == 4 || i == 8 {
+                line.push('-');
+            }
+            line.push(ALPHABET[*index as usize]);
+        }
+
+        output.push(line);
+    }
+
+    output
+}
+
+// Parity bit of a pair of octets, as checked by armax.c:alphatobin()
+pub fn parity(octet1: u32, octet2: u32) -> u32 {
+    (octet1.count_ones() + octet2.count_ones()) & 1
+}
+
+// Equivalent to armax.c:armBatchEncryptFull()
+// Apply the AR2 layer to non-verifier lines, generate the CRC16 nibble, then encrypt every address/value pair of a cheat
+pub fn encrypt_cheat(input: Cheat, armax_seeds: &[u32; 32], ar2_seeds: &mut [u8; 4]) -> Result<Cheat> {
+    if !input.codes.len().is_multiple_of(2) {
+        return Err(Error::new(ErrorKind::MissingValue).in_cheat(&input).at_line(input.codes.len() / 2));
+    }
+
+    // Clone input to update and return
+    let mut output = input.clone();
+
+    if output.codes.is_empty() {
+        return Ok(output);
+    }
+
+    // Determine ARMAX verifier code count (given two u32 code octets per line)
+    let verifier_code_count = (read_verifier_length(&output.codes).map_err(|e| e.in_cheat(&input).at_line(0))? as usize) * 2;
+
+    if output.codes.len() > verifier_code_count {
+        // Separate MAX verifier codes from non-verifier codes that still require AR2 encryption
+        let ar2_codes = output.codes.split_off(verifier_code_count);
+
+        // Encrypt all AR2 codes, then swap their bytes. Key lines change the seeds for the rest of the stream.
+        let ar2_codes: Vec<u32> = ar2::encrypt::encrypt_cheat(ar2_codes, ar2_seeds)
+            .map_err(|mut e| {
+                // Count lines from the start of the cheat, verifier included
+                e.line = e.line.map(|line| line + verifier_code_count / 2);
+                e.in_cheat(&input)
+            })?
+            .into_iter()
+            .map(swap_bytes)
+            .collect();
+
+        // Re-combine verifier codes and newly-encrypted AR2 codes
+        output.codes.extend(ar2_codes);
+    }
+
+    // Store the CRC16 nibble at the top of the 1st code
+    output.codes[0] = (output.codes[0] & 0x0FFFFFFF) | (crc_nibble(&output.codes) << 28);
+
+    // Encrypt each pair
+    for i in (0..output.codes.len()).step_by(2) {
+        let (addr, val) = encrypt_pair((output.codes[i], output.codes[i+1]), armax_seeds);
+        output.codes[i] = addr;
+        output.codes[i+1] = val;
+    }
+
+    Ok(output)
+}
+
+// Encrypt a pair of ARMAX octets. Exact inverse of decrypt::decrypt_pair().
+pub fn encrypt_pair(input: (u32, u32), seeds: &[u32; 32]) -> (u32, u32) {
+    // Swap address and value, then byte swap 1/2
+    let mut addr = swap_bytes(input.1);
+    let mut val = swap_bytes(input.0);
+
+    // Scramble 1/2 (undoes unscramble_2)
+    let scrambled = scramble_1(addr, val);
+    addr = scrambled.0;
+    val = scrambled.1;
+
+    // Apply seeds in reverse order
+    for i in (0..32).step_by(4).rev() {
+        let mut tmp = rotate_right(addr, 4) ^ seeds[i+2];
+        let mut tmp2 = addr ^ seeds[i+3];
+        val ^= octet_mask(tmp, tmp2);
+
+        tmp = rotate_right(val, 4) ^ seeds[i];
+        tmp2 = val ^ seeds[i+1];
+        addr ^= octet_mask(tmp, tmp2);
+    }
+
+    // Scramble 2/2 (undoes unscramble_1)
+    let scrambled = scramble_2(addr, val);
+    addr = scrambled.0;
+    val = scrambled.1;
+
+    // Byte swap 2/2
+    (swap_bytes(addr), swap_bytes(val))
+}
+
+// Scramble operation 1 of 2. Undoes decrypt::unscramble_2().
+pub fn scramble_1(mut addr: u32, mut val: u32) -> (u32, u32) {
+    addr = rotate_left(addr, 4);
+    let mut tmp: u32 = (addr ^ val) & 0xF0F0F0F0;
+    val ^= tmp;
+    addr ^= tmp;
+
+    addr = rotate_right(addr, 20);
+    tmp = (addr ^ val) & 0xFFFF0000;
+    val ^= tmp;
+    addr ^= tmp;
+
+    addr = rotate_right(addr, 18);
+    tmp = (addr ^ val) & 0x33333333;
+    val ^= tmp;
+    addr ^= tmp;
+
+    addr = rotate_right(addr, 6);
+    tmp = (addr ^ val) & 0x00FF00FF;
+    val ^= tmp;
+    addr ^= tmp;
+
+    addr = rotate_left(addr, 9);
+    tmp = (addr ^ val) & 0xAAAAAAAA;
+    val ^= tmp;
+    addr ^= tmp;
+
+    (addr, rotate_left(val, 1))
+}
+
+// Scramble operation 2 of 2. Undoes decrypt::unscramble_1().
+pub fn scramble_2(mut addr: u32, mut val: u32) -> (u32, u32) {
+    addr = rotate_right(addr, 1);
+    let mut tmp: u32 = (addr ^ val) & 0xAAAAAAAA;
+    addr ^= tmp;
+    val ^= tmp;
+
+    val = rotate_right(val, 9);
+    tmp = (addr ^ val) & 0x00FF00FF;
+    addr ^= tmp;
+    val ^= tmp;
+
+    val = rotate_left(val, 6);
+    tmp = (addr ^ val) & 0x33333333;
+    addr ^= tmp;
+    val ^= tmp;
+
+    val = rotate_left(val, 18);
+    tmp = (addr ^ val) & 0xFFFF0000;
+    addr ^= tmp;
+    val ^= tmp;
+
+    val = rotate_left(val, 20);
+    tmp = (addr ^ val) & 0xF0F0F0F0;
+    addr ^= tmp;
+    val ^= tmp;
+
+    (addr, rotate_right(val, 4))
+}
@@ -1,11 +1,14 @@
 pub mod decrypt;
+pub mod encrypt;
 pub mod seeds;
+pub mod verifier;
 mod table;
 
-#[derive(Clone, PartialEq)]
+// How verifiers are built for cheats converted to ARMAX
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum VerifierMode {
-    Manual,
-    Auto,
+    Manual,     // Verifiers are only built from cheat metadata
+    Auto,       // Enable code verifiers also carry the disc hash, if one is given
 }
 
 // Attempt to recognize if this string is an ARMAX code or not
@@ -24,4 +27,30 @@ pub fn rotate_right(input: u32, rot: u8) -> u32 { (input >> rot) | (input << (32
 // TODO: De-duplicate common operations
 // Original source: armax.c:byteswap()
 // Shuffle bytes around
-pub fn swap_bytes(input: u32) -> u32 { (input << 24) | ((input << 8) & 0x00FF0000) | ((input >> 8) & 0x0000FF00) | (input >> 24) }
\ No newline at end of file
+pub fn swap_bytes(input: u32) -> u32 { (input << 24) | ((input << 8) & 0x00FF0000) | ((input >> 8) & 0x0000FF00) | (input >> 24) }
+// Original source: armax.c:gencrc16()
+// CRC16 of decrypted codes, read as little-endian bytes
+pub fn gen_crc16(input: &[u32]) -> u16 {
+    let mut crc: u16 = 0;
+
+    for code in input {
+        for byte in code.to_le_bytes() {
+            let index = ((crc as u8) ^ byte) as usize;
+            crc = (crc >> 8) ^ table::CRC1[index & 0x0F] ^ table::CRC0[index >> 4];
+        }
+    }
+
+    crc
+}
+
+// CRC16 folded into the nibble stored at the top of the first decrypted code.
+// Remarks: The stored nibble itself is excluded from the calculation.
+pub fn crc_nibble(input: &[u32]) -> u32 {
+    let mut codes = input.to_vec();
+    if let Some(first) = codes.first_mut() {
+        *first &= 0x0FFFFFFF;
+    }
+
+    let crc = gen_crc16(&codes) as u32;
+    ((crc >> 12) ^ (crc >> 8) ^ (crc >> 4) ^ crc) & 0x0F
+}
@@ -0,0 +1,138 @@
+use crate::armax::decrypt::read_verifier_length;
+use crate::cheat::Cheat;
+use crate::error::{Error, ErrorKind, Result};
+use crate::game::{Game, Region};
+
+// Expansion data sizes in bits, indexed by expansion type
+pub const EXPANSION_SIZES: [u8; 8] = [
+    6,      // ?
+    10,     // ?
+    12,     // ?
+    19,     // Folder content
+    19,     // Folder content
+    8,      // Folder
+    7,      // ?
+    32,     // Disc hashes, other?
+];
+
+// Expansion type used to store disc hashes
+pub const DISC_HASH_EXPANSION: u8 = 7;
+
+// Optional data appended to a verifier after the cheat metadata
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Expansion {
+    pub kind:   u8,     // Index into EXPANSION_SIZES
+    pub data:   u32,    // Expansion data, truncated to the size of its kind
+}
+
+// Original source: armax.c:armMakeVerifier()
+// Build the verifier lines for a cheat, given its metadata and any expansions
+pub fn make_verifier(cheat: &Cheat, expansions: &[Expansion]) -> Result<Vec<u32>> {
+    // Count the bits following the cheat metadata: [ terminator | exp_index | exp_data ] ... terminator
+    let bits: u32 = expansions
+        .iter()
+        .map(|exp| 4 + EXPANSION_SIZES[(exp.kind & 7) as usize] as u32)
+        .sum::<u32>() + 1;
+
+    // Zeroed output lines, two u32 code octets per line
+    let mut output: Vec<u32> = vec![0u32; (verifier_lines(bits) as usize) * 2];
+
+    // Key array for bit string operations
+    let mut key: [u32; 3] = [
+        0u32,
+        4u32,   // Skip writing CRC bytes
+        output.len() as u32,
+    ];
+
+    // WARNING: WRITING PERMUTES THE KEY ARRAY - ORDER MATTERS!
+    write_bit_string(&mut output, &mut key, cheat.game_id, 13)?;
+    write_bit_string(&mut output, &mut key, cheat.id, 19)?;
+    write_bit_string(&mut output, &mut key, cheat.enable_code as u32, 1)?;
+    write_bit_string(&mut output, &mut key, 0, 1)?;    // Unknown
+    write_bit_string(&mut output, &mut key, cheat.region as u32, 2)?;
+
+    // Write each expansion, preceded by a cleared terminator bit
+    for exp in expansions {
+        let kind = exp.kind & 7;
+        write_bit_string(&mut output, &mut key, 0, 1)?;
+        write_bit_string(&mut output, &mut key, kind as u32, 3)?;
+        write_bit_string(&mut output, &mut key, exp.data, EXPANSION_SIZES[kind as usize])?;
+    }
+
+    // Final verifier terminator
+    write_bit_string(&mut output, &mut key, 1, 1)?;
+
+    Ok(output)
+}
+
+// Build a decrypted ARMAX cheat from a cheat of raw codes and the game it belongs to
+pub fn add_verifier(raw: &Cheat, game: &Game, expansions: &[Expansion]) -> Result<Cheat> {
+    // Clone input to update and return
+    let mut output = raw.clone();
+
+    // Take game metadata from the parent game
+    output.game_id = game.id & 0x1FFF;
+    output.region = match game.region {
+        Region::USA => 0,
+        Region::PAL => 1,
+        Region::Japan => 2,
+        Region::Unknown => raw.region,
+    };
+
+    // Prepend verifier lines to the raw codes
+    let mut codes = make_verifier(&output, expansions).map_err(|e| e.in_cheat(raw))?;
+    codes.extend_from_slice(&raw.codes);
+    output.codes = codes;
+
+    Ok(output)
+}
+
+// Strip the verifier lines from a decrypted ARMAX cheat, leaving its raw codes
+pub fn remove_verifier(cheat: &Cheat) -> Result<Cheat> {
+    let lines = read_verifier_length(&cheat.codes).map_err(|e| e.in_cheat(cheat).at_line(0))? as usize;
+
+    // Clone input to update and return
+    let mut output = cheat.clone();
+    output.codes = output.codes.split_off((lines * 2).min(cheat.codes.len()));
+    output.spans = output.spans.split_off(lines.min(cheat.spans.len()));
+
+    Ok(output)
+}
+
+// Expansions for a cheat's verifier. Only enable codes carry the disc hash.
+pub fn expansions_for(cheat: &Cheat, disc_hash: Option<u32>) -> Vec<Expansion> {
+    match disc_hash {
+        Some(hash) if cheat.enable_code => vec!(Expansion { kind: DISC_HASH_EXPANSION, data: hash }),
+        _ => vec!(),
+    }
+}
+
+// Number of lines occupied by a verifier, given the bits following the cheat metadata.
+// Remarks: Matches decrypt::read_verifier_length(), including its rounding.
+fn verifier_lines(bits: u32) -> u32 {
+    // There's only 24 bits on the first line for [ terminator | exp_index | exp_data ]
+    let mut lines = 1;
+    if bits >= 24 {
+        // Count additional lines occupied
+        lines += 1 + (bits - 24) / 64;
+    }
+    lines
+}
+
+// Original source: armax.c:writebitstring()
+// Write the lowest bits of a u32 to arbitrary indexes within a Vec<u32>
+pub fn write_bit_string(output: &mut [u32], ctrl: &mut [u32; 3], value: u32, length: u8) -> Result<()> {
+    for i in (0..length).rev() {
+        if ctrl[1] > 31 {
+            ctrl[1] = 0;
+            ctrl[0] += 1;
+        }
+        if ctrl[0] >= ctrl[2] || ctrl[0] as usize >= output.len() {
+            return Err(Error::new(ErrorKind::BitString { length }));
+        }
+        output[ctrl[0] as usize] |= ((value >> i) & 1) << (31 - ctrl[1]);
+        ctrl[1] += 1;
+    }
+
+    Ok(())
+}
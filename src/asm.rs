@@ -0,0 +1,274 @@
+use crate::cheat::{Cheat, CheatStates};
+use crate::error::{Error, ErrorKind, Result};
+use crate::formats::CodeDevice;
+use crate::ir::{self, Compare, Instruction, Skip, Width, BUTTONS};
+use crate::token::{split_line, Span, COMMENT_PREFIXES};
+
+// Assemble cheats written as instructions into the decrypted codes of a device
+// Example:
+//  Infinite Health
+//  # Hold L1 + R1 to refill
+//  joker 0x003B4FE2 L1+R1 then 1
+//  write16 0x004A0000 = 0x03E7
+//
+// Instructions:
+//  write8/16/32 ADDR = VALUE                       ptr8/16/32 POINTER + OFFSET = VALUE
+//  inc8/16/32 ADDR += VALUE                        dec8/16/32 ADDR -= VALUE
+//  fill8/16/32 ADDR = VALUE count N [stride BYTES] [step VALUE]
+//  copy SRC to DST count BYTES                     hook ADDR = VALUE
+//  if8/16/32 ADDR (==|!=|<|>|<s|>s|&) VALUE then (N|all)
+//  joker PAD BUTTON+BUTTON... then (N|all)         endif
+//
+// Lines starting with #, // or ; are added to the cheat's comment. Any other line names a new cheat, unless it looks
+// like a mistyped instruction, i.e. has an = or a 0x number in it.
+// Conditionals skip a number of following instructions, however many code lines they take up on the device.
+pub fn assemble(text: &str, device: CodeDevice) -> Result<Vec<Cheat>> {
+    let mut output: Vec<Cheat> = vec!();
+
+    // Cheat being read, with its instructions and where each was read from
+    let mut cheat = Cheat::new();
+    let mut instructions: Vec<(usize, Instruction)> = vec!();
+    let mut spans: Vec<Span> = vec!();
+    let mut named = false;
+
+    for (i, line) in text.lines().enumerate() {
+        let tokens = split_line(line, i + 1);
+        let Some(&(first, first_span)) = tokens.first() else {
+            continue;
+        };
+
+        if let Some(comment) = COMMENT_PREFIXES.iter().find_map(|prefix| line.trim().strip_prefix(prefix)) {
+            if !cheat.comment.is_empty() {
+                cheat.comment.push('\n');
+            }
+            cheat.comment.push_str(comment.trim());
+        }
+        else if is_mnemonic(first) {
+            let mut reader = Reader { tokens: &tokens[1..], end: first_span.at(line.chars().count() - first_span.column + 1) };
+            instructions.push((spans.len(), read_instruction(first, &mut reader).map_err(|e| e.with_span(first_span))?));
+            reader.finish()?;
+            spans.push(first_span.to(tokens[tokens.len() - 1].1));
+        }
+        else if tokens.iter().any(|(word, _)| is_operand(word)) {
+            return Err(syntax("instruction mnemonic", first_span));
+        }
+        else {
+            // Cheat names start a new cheat, once the current one has a name or instructions
+            if named || !instructions.is_empty() {
+                output.push(finish(cheat, &instructions, &spans, device)?);
+                cheat = Cheat::new();
+                instructions.clear();
+                spans.clear();
+            }
+            cheat.name = line.trim().to_string();
+            named = true;
+        }
+    }
+
+    if named || !instructions.is_empty() {
+        output.push(finish(cheat, &instructions, &spans, device)?);
+    }
+
+    Ok(output)
+}
+
+// Encode the instructions of a cheat for a device
+fn finish(input: Cheat, instructions: &[(usize, Instruction)], spans: &[Span], device: CodeDevice) -> Result<Cheat> {
+    // Clone input to update and return
+    let mut output = input.clone();
+
+    let encoded = ir::encode_lines(device, instructions)
+        .map_err(|e| e.in_cheat(&input).locate(spans))?;
+
+    for (span, codes) in spans.iter().zip(&encoded) {
+        output.spans.extend(std::iter::repeat_n(*span, codes.len() / 2));
+    }
+    output.codes = encoded.concat();
+    output.enable_code = instructions.iter().any(|(_, inst)| matches!(inst, Instruction::Hook { .. }));
+    output.state = CheatStates::Translated;
+
+    Ok(output)
+}
+
+fn is_mnemonic(word: &str) -> bool {
+    ["write", "inc", "dec", "fill", "ptr", "if"].iter().any(|prefix| width(word, prefix).is_some())
+        || ["copy", "joker", "endif", "hook"].contains(&word)
+}
+
+// Operators and 0x numbers, which only appear in instructions
+fn is_operand(word: &str) -> bool {
+    let hex = word.strip_prefix("0x").or_else(|| word.strip_prefix("0X"));
+    word.contains('=') || hex.is_some_and(|hex| !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+// Width given by a mnemonic's suffix, e.g. "write16"
+fn width(word: &str, prefix: &str) -> Option<Width> {
+    match word.strip_prefix(prefix)? {
+        "8" => Some(Width::Bits8),
+        "16" => Some(Width::Bits16),
+        "32" => Some(Width::Bits32),
+        _ => None,
+    }
+}
+
+// Read a single instruction, given its mnemonic
+fn read_instruction(mnemonic: &str, reader: &mut Reader) -> Result<Instruction> {
+    let sized = |prefix| width(mnemonic, prefix);
+
+    let output = if let Some(width) = sized("write") {
+        let addr = reader.number("address")?;
+        reader.keyword("=")?;
+        Instruction::write(width, addr, reader.value(width)?)
+    }
+    else if let Some(width) = sized("inc") {
+        let addr = reader.number("address")?;
+        reader.keyword("+=")?;
+        Instruction::Increment { width, addr, value: reader.value(width)? }
+    }
+    else if let Some(width) = sized("dec") {
+        let addr = reader.number("address")?;
+        reader.keyword("-=")?;
+        Instruction::Decrement { width, addr, value: reader.value(width)? }
+    }
+    else if let Some(width) = sized("fill") {
+        let addr = reader.number("address")?;
+        reader.keyword("=")?;
+        let value = reader.value(width)?;
+        reader.keyword("count")?;
+        let count = reader.number("count")?;
+        let stride = if reader.optional("stride") { reader.number("stride")? } else { width.bytes() };
+        let step = if reader.optional("step") { reader.value(width)? } else { 0 };
+        Instruction::MultiWrite { width, addr, value, count, stride, step }
+    }
+    else if let Some(width) = sized("ptr") {
+        let pointer = reader.number("pointer address")?;
+        reader.keyword("+")?;
+        let offset = reader.number("offset")?;
+        reader.keyword("=")?;
+        Instruction::PointerWrite { width, pointer, offset, value: reader.value(width)? }
+    }
+    else if let Some(width) = sized("if") {
+        let addr = reader.number("address")?;
+        let cmp = match reader.next("comparison")? {
+            ("==", _) => Compare::Equal,
+            ("!=", _) => Compare::NotEqual,
+            ("<", _) => Compare::Less,
+            (">", _) => Compare::Greater,
+            ("<s", _) => Compare::LessSigned,
+            (">s", _) => Compare::GreaterSigned,
+            ("&", _) => Compare::And,
+            (_, span) => return Err(syntax("comparison (==, !=, <, >, <s, >s or &)", span)),
+        };
+        let value = reader.value(width)?;
+        Instruction::If { cmp, width, addr, value, skip_lines: reader.skip()? }
+    }
+    else {
+        match mnemonic {
+            "copy" => {
+                let src = reader.number("source address")?;
+                reader.keyword("to")?;
+                let dst = reader.number("destination address")?;
+                reader.keyword("count")?;
+                Instruction::CopyBytes { src, dst, count: reader.number("byte count")? }
+            },
+            "joker" => {
+                let addr = reader.number("pad address")?;
+                let buttons = reader.buttons()?;
+                Instruction::Joker { addr, buttons, skip_lines: reader.skip()? }
+            },
+            "hook" => {
+                let addr = reader.number("address")?;
+                reader.keyword("=")?;
+                Instruction::Hook { addr, value: reader.number("value")? }
+            },
+            _ => Instruction::EndIf,
+        }
+    };
+
+    Ok(output)
+}
+
+fn syntax(expected: &'static str, span: Span) -> Error {
+    Error::new(ErrorKind::Syntax { expected }).with_span(span)
+}
+
+// Reads the tokens after a mnemonic
+struct Reader<'a> {
+    tokens: &'a [(&'a str, Span)],
+    end:    Span,       // Just past the end of the line, for missing tokens
+}
+
+impl<'a> Reader<'a> {
+    fn next(&mut self, expected: &'static str) -> Result<(&'a str, Span)> {
+        let (first, rest) = self.tokens.split_first().ok_or_else(|| syntax(expected, self.end))?;
+        self.tokens = rest;
+        Ok(*first)
+    }
+
+    fn keyword(&mut self, keyword: &'static str) -> Result<()> {
+        match self.next(keyword)? {
+            (word, _) if word == keyword => Ok(()),
+            (_, span) => Err(syntax(keyword, span)),
+        }
+    }
+
+    // Read a keyword if it's next
+    fn optional(&mut self, keyword: &str) -> bool {
+        match self.tokens.first() {
+            Some((word, _)) if *word == keyword => {
+                self.tokens = &self.tokens[1..];
+                true
+            },
+            _ => false,
+        }
+    }
+
+    // Hex number with a 0x prefix, or a decimal one
+    fn number(&mut self, expected: &'static str) -> Result<u32> {
+        let (word, span) = self.next(expected)?;
+        let parsed = match word.strip_prefix("0x").or_else(|| word.strip_prefix("0X")) {
+            Some(hex) => u32::from_str_radix(hex, 16),
+            None => word.parse::<u32>(),
+        };
+        parsed.map_err(|_| syntax(expected, span))
+    }
+
+    // Number that fits in a width
+    fn value(&mut self, width: Width) -> Result<u32> {
+        let span = self.tokens.first().map(|(_, span)| *span).unwrap_or(self.end);
+        match self.number("value")? {
+            value if value & !width.mask() == 0 => Ok(value),
+            _ => Err(syntax("value that fits the instruction's width", span)),
+        }
+    }
+
+    // Button names joined by +, e.g. L1+R1
+    fn buttons(&mut self) -> Result<u16> {
+        let (word, span) = self.next("buttons")?;
+        let mut output: u16 = 0;
+        for name in word.split('+') {
+            let (bit, _) = BUTTONS.iter()
+                .find(|(_, button)| button.eq_ignore_ascii_case(name))
+                .ok_or_else(|| syntax("button names joined by +, e.g. L1+R1", span))?;
+            output |= bit;
+        }
+        Ok(output)
+    }
+
+    // Instructions a conditional skips: then N, or then all
+    fn skip(&mut self) -> Result<Skip> {
+        self.keyword("then")?;
+        if self.optional("all") {
+            return Ok(Skip::All);
+        }
+        Ok(Skip::Lines(self.number("number of instructions, or all")? as usize))
+    }
+
+    // Make sure nothing follows the instruction
+    fn finish(&self) -> Result<()> {
+        match self.tokens.first() {
+            Some((_, span)) => Err(syntax("end of line", *span)),
+            None => Ok(()),
+        }
+    }
+}
@@ -1,4 +1,6 @@
-#[derive(Clone)]
+use crate::token::Span;
+
+#[derive(Clone, Debug)]
 pub struct Cheat {
     pub game_id:        u32,            //  Parent Game ID
     pub region:         u8,             //  Game region
@@ -7,11 +9,13 @@ pub struct Cheat {
     pub comment:        String,         //  Cheat comment(s)
     pub flags:          [u8; 3],        //  TODO: Remove Cheat flags?
     pub enable_code:    bool,           //  Whether this code is the 'Master Code'
+    pub crc_valid:      Option<bool>,   //  Whether the decrypted codes matched their CRC16. Only ARMAX codes carry one.
     pub codes:          Vec<u32>,       //  Codes composing this cheat
+    pub spans:          Vec<Span>,      //  Where each code line was read from, if parsed from text
     pub state:          CheatStates,    //  Decryption/translation state
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum CheatStates {
     Unverified,
     Parsed,
@@ -19,6 +23,12 @@ pub enum CheatStates {
     Translated,
 }
 
+impl Default for Cheat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Cheat {
     // Original source: cheat.c:cheatInit()
     pub fn new() -> Self {
@@ -30,7 +40,9 @@ impl Cheat {
             comment: "".to_string(),
             flags: [0u8; 3],
             enable_code: false,
+            crc_valid: None,
             codes: vec![],
+            spans: vec![],
             state: CheatStates::Unverified
         }
     }
@@ -0,0 +1,233 @@
+use crate::armax;
+use crate::ar2::{self, KEY_ADDR};
+use crate::cheat::Cheat;
+use crate::formats::{CodeDevice, CodeFormat, CodeType, FORMATS};
+use crate::ir::{self, Instruction};
+use crate::omniconvert::{self, State};
+use crate::token::TokenType;
+
+// Memory games actually patch: EE RAM past the kernel
+const PLAUSIBLE_ADDRESSES: std::ops::Range<u32> = 0x00100000..0x02000000;
+
+// Address of the CodeBreaker line enabling V7 encryption
+const BEEFCODE: u32 = 0xBEEFC0DE;
+
+// A code type some input might be written in
+#[derive(Clone)]
+pub struct Guess {
+    pub code:       CodeType,       // Entry of FORMATS
+    pub score:      u8,             // Out of 100
+    pub reasons:    Vec<String>,    // What the score is based on
+}
+
+// Score a block of cheats against every code type, best guess first
+// Remarks: Code types that can't read the input at all are left out. Some code types share a format and device
+// (e.g. Action Replay V1 and Interact GameShark V1), so they always get the same score, in FORMATS order.
+pub fn detect_format(text: &str) -> Vec<Guess> {
+    let mut state = State::new();
+
+    // Cheats read each way, parsed once
+    let armax_cheats = read(text, CodeFormat::ARMAX);
+    let raw_cheats = read(text, CodeFormat::RAW);
+
+    let mut output: Vec<Guess> = vec!();
+    for code in FORMATS.iter() {
+        let cheats = match code.format {
+            CodeFormat::ARMAX => &armax_cheats,
+            _ => &raw_cheats,
+        };
+        if let Some(guess) = score(&mut state, code, cheats) {
+            output.push(guess);
+        }
+        else if code.format == CodeFormat::ARMAX && looks_like_armax(text) {
+            // Most likely a typo, which convert() will point out
+            output.push(Guess {
+                code: code.clone(),
+                score: 10,
+                reasons: vec!("looks like ARMAX codes, but some lines fail the parity check".to_string()),
+            });
+        }
+    }
+
+    // Stable, so equal scores stay in FORMATS order
+    output.sort_by_key(|guess| std::cmp::Reverse(guess.score));
+    output
+}
+
+fn looks_like_armax(text: &str) -> bool {
+    omniconvert::read_input(text, CodeFormat::ARMAX).iter().any(|token| token.types.contains(&TokenType::ARMAXCode))
+}
+
+// Cheats with codes read from the input, if it reads as the format at all
+fn read(text: &str, format: CodeFormat) -> Option<Vec<Cheat>> {
+    let cheats = omniconvert::build_cheat_list(omniconvert::read_input(text, format), &mut vec!()).ok()?;
+    match cheats.iter().any(|cheat| !cheat.codes.is_empty()) {
+        true => Some(cheats),
+        false => None,
+    }
+}
+
+fn score(state: &mut State, code: &CodeType, cheats: &Option<Vec<Cheat>>) -> Option<Guess> {
+    let cheats = cheats.as_ref()?;
+    let mut reasons: Vec<String> = vec!();
+    let mut bonus = 0;
+
+    // Token shapes
+    reasons.push(match code.format {
+        CodeFormat::ARMAX => "reads as ARMAX codes with valid parity".to_string(),
+        _ => "reads as address/value pairs".to_string(),
+    });
+
+    // Key lines are left as they are by the CodeBreaker cipher, but encrypted along with everything else by AR2
+    let pairs: Vec<&[u32]> = cheats.iter().flat_map(|cheat| cheat.codes.chunks_exact(2)).collect();
+    let beefcodes: Vec<&[u32]> = pairs.iter().copied().filter(|pair| pair[0] | 1 == BEEFCODE | 1).collect();
+    let common_key = beefcodes.iter().all(|pair| pair == &[BEEFCODE, 0]);
+    match code.format {
+        CodeFormat::CB7 if !beefcodes.is_empty() && common_key => {
+            bonus += 30;
+            reasons.push("has a BEEFC0DE header for the common V7 key".to_string());
+        },
+        CodeFormat::CB if !beefcodes.is_empty() => {
+            bonus += if common_key { 20 } else { 30 };
+            reasons.push("has BEEFC0DE key lines".to_string());
+        },
+        CodeFormat::RAW if code.device == CodeDevice::AR2 && pairs.iter().any(|pair| pair[0] == KEY_ADDR) => {
+            bonus += 30;
+            reasons.push("has unencrypted DEADFACE key lines".to_string());
+        },
+        _ => {},
+    }
+
+    // Unencrypted ARMAX codes carry no CRC, but cheats for one game share the game ID in their verifiers
+    let game_id = match code.format {
+        CodeFormat::MAXRAW => shared_game_id(cheats),
+        _ => None,
+    };
+
+    // Decrypt every cheat, then see whether the codes make sense for the device
+    state.reset_devices();
+    let mut plausible = 0;
+    let mut lines = 0;
+    let mut crc_valid = 0;
+    let mut keys = 0;
+    for cheat in cheats.iter().filter(|cheat| !cheat.codes.is_empty()) {
+        // AR2 keys are only seen while decrypting
+        if code.format == CodeFormat::AR2 {
+            keys += decrypted_keys(state, cheat);
+        }
+
+        // Key lines are dropped while decrypting, and don't count either way
+        let Ok(decrypted) = omniconvert::decrypt_cheat(state, code.format, cheat.clone()) else {
+            lines += cheat.codes.len() / 2;
+            continue;
+        };
+        lines += decrypted.codes.len() / 2;
+
+        // Nearly any codes decode on ARMAX, so only trust the ones that match their CRC or game
+        match code.format {
+            CodeFormat::ARMAX if decrypted.crc_valid == Some(true) => crc_valid += 1,
+            CodeFormat::MAXRAW if game_id.is_some_and(|(id, _)| verifier_game_id(&decrypted) == Some(id)) => {},
+            CodeFormat::ARMAX | CodeFormat::MAXRAW => continue,
+            _ => {},
+        }
+        plausible += plausible_lines(&decrypted, code.device);
+    }
+    let cheat_count = cheats.iter().filter(|cheat| !cheat.codes.is_empty()).count();
+
+    // Weaker than a CRC, as any block with similar first lines shares one
+    if let Some((id, count)) = game_id.filter(|(_, count)| *count > 1) {
+        bonus += 20 * count / cheat_count;
+        reasons.push(format!("game ID 0x{:04X} is shared by {} of {} cheats", id, count, cheat_count));
+    }
+    if crc_valid > 0 {
+        bonus += 30 * crc_valid / cheat_count;
+        reasons.push(format!("CRC matches for {} of {} cheats", crc_valid, cheat_count));
+    }
+    if keys > 0 {
+        bonus += 30;
+        reasons.push("decrypts DEADFACE key lines".to_string());
+    }
+    reasons.push(format!("{} of {} lines decrypt to plausible codes", plausible, lines));
+
+    Some(Guess {
+        code: code.clone(),
+        score: (60 * plausible / lines.max(1) + bonus).min(100) as u8,
+        reasons,
+    })
+}
+
+// Game ID read from the verifier of a decrypted ARMAX cheat, if it has a valid region
+fn verifier_game_id(cheat: &Cheat) -> Option<u32> {
+    armax::decrypt::read_cheat_meta(cheat, &cheat.codes).ok()
+        .filter(|meta| meta.region <= 2)
+        .map(|meta| meta.game_id)
+}
+
+// Game ID most of the cheats have in their verifiers, and how many have it
+fn shared_game_id(cheats: &[Cheat]) -> Option<(u32, usize)> {
+    let ids: Vec<u32> = cheats.iter().filter_map(verifier_game_id).collect();
+    ids.iter()
+        .map(|id| (*id, ids.iter().filter(|other| *other == id).count()))
+        .max_by_key(|(_, count)| *count)
+}
+
+// Number of DEADFACE key lines an AR2 cheat decrypts to, following key changes
+fn decrypted_keys(state: &State, cheat: &Cheat) -> usize {
+    let mut seeds = state.ar2_input;
+    cheat.codes.chunks_exact(2)
+        .filter(|pair| {
+            let key = ar2::decrypt::decrypt_code(pair[0], seeds[0], seeds[1]).is_ok_and(|addr| addr == KEY_ADDR);
+            if key {
+                if let Ok(value) = ar2::decrypt::decrypt_code(pair[1], seeds[2], seeds[3]) {
+                    seeds = ar2::seeds::regenerate(value);
+                }
+            }
+            key
+        })
+        .count()
+}
+
+// Number of decrypted lines that decode to instructions on memory a game could use
+fn plausible_lines(cheat: &Cheat, device: CodeDevice) -> usize {
+    let codes = match device {
+        CodeDevice::ARMAX => match armax::verifier::remove_verifier(cheat) {
+            Ok(raw) => raw.codes,
+            Err(_) => return 0,
+        },
+        _ => cheat.codes.clone(),
+    };
+
+    // A verifier that reads is as plausible as the codes after it
+    let mut output = (cheat.codes.len() - codes.len()) / 2;
+
+    let Ok(instructions) = ir::decode_lines(device, &codes) else {
+        return 0;
+    };
+
+    // Count the lines each instruction takes up, as it starts where the next one does
+    for (i, (start, inst)) in instructions.iter().enumerate() {
+        let end = instructions.get(i + 1).map(|(next, _)| *next).unwrap_or(codes.len() / 2);
+        if addresses(inst).iter().all(|addr| PLAUSIBLE_ADDRESSES.contains(addr)) {
+            output += end - start;
+        }
+    }
+    output
+}
+
+// Memory addresses an instruction reads or writes
+fn addresses(inst: &Instruction) -> Vec<u32> {
+    match *inst {
+        Instruction::Write8 { addr, .. }
+        | Instruction::Write16 { addr, .. }
+        | Instruction::Write32 { addr, .. }
+        | Instruction::Increment { addr, .. }
+        | Instruction::Decrement { addr, .. }
+        | Instruction::MultiWrite { addr, .. }
+        | Instruction::If { addr, .. }
+        | Instruction::Joker { addr, .. }
+        | Instruction::Hook { addr, .. } => vec!(addr),
+        Instruction::PointerWrite { pointer, .. } => vec!(pointer),
+        Instruction::CopyBytes { src, dst, .. } => vec!(src, dst),
+        Instruction::EndIf => vec!(),
+    }
+}
@@ -0,0 +1,168 @@
+use crate::armax::decrypt::read_verifier_length;
+use crate::cheat::Cheat;
+use crate::error::Result;
+use crate::formats::CodeDevice;
+use crate::ir::{self, Compare, Instruction, Skip, Width, BUTTONS};
+
+// Describe every decrypted code line of a cheat, e.g. "003F38AB 0000007F  8-bit write 0x7F → 0x003F38AB"
+// Remarks: ARMAX cheats are expected to still carry their verifier
+pub fn disassemble(cheat: &Cheat, device: CodeDevice) -> Result<Vec<String>> {
+    let pairs: Vec<&[u32]> = cheat.codes.chunks(2).collect();
+    let mut text: Vec<Option<String>> = vec![None; pairs.len()];
+
+    // Verifier lines come before any codes
+    let mut first = 0;
+    if device == CodeDevice::ARMAX {
+        first = (read_verifier_length(&cheat.codes).map_err(|e| e.in_cheat(cheat).at_line(0))? as usize).min(pairs.len());
+        for (line, text) in text.iter_mut().enumerate().take(first) {
+            *text = Some(match line {
+                0 => describe_verifier(cheat),
+                _ => "verifier (continued)".to_string(),
+            });
+        }
+    }
+
+    let instructions = ir::decode_lines(device, &cheat.codes[(first * 2).min(cheat.codes.len())..])
+        .map_err(|mut e| {
+            e.line = e.line.map(|line| line + first);
+            e.in_cheat(cheat)
+        })?;
+
+    for (i, (start, inst)) in instructions.iter().enumerate() {
+        let start = start + first;
+        let end = instructions.get(i + 1).map(|(next, _)| next + first).unwrap_or(pairs.len());
+
+        // Conditionals skip however many lines the instructions after them take up
+        let skipped = match inst {
+            Instruction::If { skip_lines: Skip::Lines(count), .. } => {
+                let last = instructions.get(i + 1 + count).map(|(next, _)| next + first).unwrap_or(pairs.len());
+                Some(last - end)
+            },
+            _ => None,
+        };
+
+        text[start] = Some(describe(inst, skipped));
+        for line in start + 1..end {
+            // ARMAX terminators aren't part of any instruction
+            if device == CodeDevice::ARMAX && pairs[line] == [0, 0] {
+                continue;
+            }
+            text[line] = Some("(continued)".to_string());
+        }
+    }
+
+    // Lines not read as instructions, like the ARMAX terminator
+    let output = pairs.iter().zip(text).map(|(pair, text)| {
+        let text = text.unwrap_or_else(|| "end of codes".to_string());
+        match pair {
+            [addr, val] => format!("{:08X} {:08X}  {}", addr, val, text),
+            _ => format!("{:08X}           {}", pair[0], text),
+        }
+    }).collect();
+
+    Ok(output)
+}
+
+// Describe a single instruction. Conditionals are given the number of lines they skip, if known.
+pub fn describe(inst: &Instruction, skipped: Option<usize>) -> String {
+    match *inst {
+        Instruction::Write8 { addr, value } => format!("8-bit write {} → 0x{:08X}", hex(Width::Bits8, value), addr),
+        Instruction::Write16 { addr, value } => format!("16-bit write {} → 0x{:08X}", hex(Width::Bits16, value), addr),
+        Instruction::Write32 { addr, value } => format!("32-bit write {} → 0x{:08X}", hex(Width::Bits32, value), addr),
+        Instruction::Increment { width, addr, value } => {
+            format!("{} increment 0x{:08X} by {}", bits(width), addr, hex(width, value))
+        },
+        Instruction::Decrement { width, addr, value } => {
+            format!("{} decrement 0x{:08X} by {}", bits(width), addr, hex(width, value))
+        },
+        Instruction::MultiWrite { width, addr, value, count, stride, step } => {
+            let mut output = format!("{} write {} → 0x{:08X}, {} times, {} bytes apart", bits(width), hex(width, value), addr, count, stride as i32);
+            if step != 0 {
+                output += &format!(", adding {} each time", hex(width, step));
+            }
+            output
+        },
+        Instruction::CopyBytes { src, dst, count } => {
+            format!("copy 0x{:X} bytes 0x{:08X} → 0x{:08X}", count, src, dst)
+        },
+        Instruction::PointerWrite { width, pointer, offset, value } => {
+            format!("{} write {} → pointer at 0x{:08X} + 0x{:X}", bits(width), hex(width, value), pointer, offset)
+        },
+        Instruction::If { cmp, width, addr, value, skip_lines } => {
+            let mut output = format!("if {} at 0x{:08X} {} {}, {}", bits(width), addr, operator(cmp), hex(width, value), run(skip_lines, skipped));
+
+            // Jokers are conditionals on the pad state, which clears the bits of held buttons
+            if width == Width::Bits16 && cmp == Compare::Equal {
+                let held = !(value as u16);
+                if (1..=4).contains(&held.count_ones()) {
+                    output += &format!(" (joker for {}?)", button_names(held));
+                }
+            }
+            output
+        },
+        Instruction::Joker { addr, buttons, skip_lines } => {
+            format!("if {} are held (pad at 0x{:08X}), {}", button_names(buttons), addr, run(skip_lines, skipped))
+        },
+        Instruction::EndIf => "end of conditional block".to_string(),
+        Instruction::Hook { addr, value } => format!("hook 0x{:08X} with 0x{:08X} (enable code)", addr, value),
+    }
+}
+
+// Names of the buttons in a mask, e.g. "L1 + R1"
+pub fn button_names(buttons: u16) -> String {
+    BUTTONS.iter()
+        .filter(|(bit, _)| buttons & bit != 0)
+        .map(|(_, name)| *name)
+        .collect::<Vec<&str>>()
+        .join(" + ")
+}
+
+// Describe the metadata stored in an ARMAX verifier
+fn describe_verifier(cheat: &Cheat) -> String {
+    let region = match cheat.region {
+        0 => "USA",
+        1 => "PAL",
+        2 => "Japan",
+        _ => "unknown region",
+    };
+    let mut output = format!("verifier: game 0x{:04X}, cheat 0x{:05X}, {}", cheat.game_id, cheat.id, region);
+    if cheat.enable_code {
+        output += ", enable code";
+    }
+    output
+}
+
+fn run(skip_lines: Skip, skipped: Option<usize>) -> String {
+    match (skip_lines, skipped) {
+        (Skip::All, _) => "run every following line".to_string(),
+        (_, Some(1)) => "run the next line".to_string(),
+        (_, Some(lines)) => format!("run the next {} lines", lines),
+        (Skip::Lines(1), None) => "run the next instruction".to_string(),
+        (Skip::Lines(count), None) => format!("run the next {} instructions", count),
+    }
+}
+
+fn operator(cmp: Compare) -> &'static str {
+    match cmp {
+        Compare::Equal => "==",
+        Compare::NotEqual => "!=",
+        Compare::Less => "<",
+        Compare::Greater => ">",
+        Compare::LessSigned => "< (signed)",
+        Compare::GreaterSigned => "> (signed)",
+        Compare::And => "&",
+    }
+}
+
+fn bits(width: Width) -> &'static str {
+    match width {
+        Width::Bits8 => "8-bit",
+        Width::Bits16 => "16-bit",
+        Width::Bits32 => "32-bit",
+    }
+}
+
+// Value padded to the digits of its width
+fn hex(width: Width, value: u32) -> String {
+    format!("0x{:0digits$X}", value & width.mask(), digits = width.bytes() as usize * 2)
+}
@@ -0,0 +1,298 @@
+use std::fmt;
+
+use crate::cheat::Cheat;
+use crate::token::{Span, TokenType};
+
+// Result type used throughout the conversion pipeline
+pub type Result<T> = std::result::Result<T, Error>;
+
+// Problems found while parsing, decrypting or encrypting codes
+#[derive(Clone, Debug, PartialEq)]
+pub enum ErrorKind {
+    InvalidCharacter { character: char, index: usize },     // ARMAX line has a character outside the cipher alphabet
+    LineTooShort { length: usize },                         // ARMAX line is missing characters
+    Parity { octets: (u32, u32) },                          // ARMAX line failed its parity check
+    BitString { length: u8 },                               // ARMAX verifier bit string runs past the end of the codes
+    InvalidHex,                                             // Code address or value isn't 8 hex digits
+    MissingValue,                                           // Code address without a value
+    NoCodes,                                                // Nothing to decrypt
+    Crc,                                                    // ARMAX cheat doesn't match its CRC16
+    ControlValue(u8),                                       // Unrecognized AR2 control value
+    SeedValue(u8),                                          // AR2 seed outside of the seed tables
+    Skipped(WarningKind),                                   // Input skipped over while parsing strictly
+    InvalidOption { option: &'static str, reason: &'static str },   // Conversion option that can't be used
+    UnknownCode(u32),                                       // Code type the input device doesn't have
+    Untranslatable(&'static str),                           // Operation the output device has no code type for
+    Syntax { expected: &'static str },                      // Assembler input that isn't a valid instruction
+    UnverifiedCipher(&'static str),                         // Cipher not yet checked against codes from the real device
+}
+
+// Error with the cheat and code line it was found in, when known
+#[derive(Clone, Debug, PartialEq)]
+pub struct Error {
+    pub kind:   ErrorKind,
+    pub cheat:  Option<String>,     // Name of the failing cheat
+    pub line:   Option<usize>,      // Index of the failing code line within the cheat
+    pub span:   Option<Span>,       // Where the failing text was read from
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind) -> Self {
+        Error {
+            kind,
+            cheat: None,
+            line: None,
+            span: None,
+        }
+    }
+
+    // Attach the failing cheat, keeping any cheat already attached
+    pub fn in_cheat(mut self, cheat: &Cheat) -> Self {
+        if self.cheat.is_none() {
+            self.cheat = Some(cheat.name.clone());
+        }
+        self
+    }
+
+    // Attach the failing code line, keeping any line already attached
+    pub fn at_line(mut self, line: usize) -> Self {
+        if self.line.is_none() {
+            self.line = Some(line);
+        }
+        self
+    }
+
+    // Attach the failing text, keeping any span already attached
+    pub fn with_span(mut self, span: Span) -> Self {
+        if self.span.is_none() {
+            self.span = Some(span);
+        }
+        self
+    }
+
+    // Attach the span of the failing code line, given the spans of a cheat's code lines
+    pub fn locate(self, spans: &[Span]) -> Self {
+        match self.line.and_then(|line| spans.get(line)) {
+            Some(span) => self.with_span(*span),
+            None => self,
+        }
+    }
+
+    // Render the error like a compiler diagnostic, quoting the failing line of the source text
+    // Example:
+    //  error: parity mismatch in 2nd ARMAX line of cheat 'Save Anywhere'
+    //    --> 14:1
+    //     |
+    //  14 | 3WQR-X7EE-ADTJB
+    //     | ^^^^^^^^^^^^^^^
+    //     |
+    //     = hint: one of the characters on this line is probably mistyped
+    pub fn render(&self, source: &str) -> String {
+        format!("error: {}\n{}", self, snippet(self.span, source, self.kind.hint()))
+    }
+}
+
+// Problems that don't stop a conversion, e.g. input that was skipped over
+#[derive(Clone, Debug, PartialEq)]
+pub enum WarningKind {
+    SkippedToken { token: String, types: Vec<TokenType> },    // Token that isn't part of any cheat
+    HexAsText { token: String },                                // Hex octet read as part of a name or comment
+    SkippedText { text: String },                               // Name or comment after the last code
+    DroppedEnableCode { name: String },                         // Enable code the output device has no code types for
+    CrcMismatch { name: String },                               // ARMAX cheat that doesn't match its CRC16
+}
+
+// Warning with where the text it's about was read from
+#[derive(Clone, Debug, PartialEq)]
+pub struct Warning {
+    pub kind:   WarningKind,
+    pub span:   Span,
+}
+
+impl Warning {
+    // Render the warning like a compiler diagnostic, quoting its line of the source text
+    pub fn render(&self, source: &str) -> String {
+        let hint = match self.kind {
+            WarningKind::SkippedToken { .. } => "this text isn't part of any cheat name, comment or code",
+            WarningKind::HexAsText { .. } => "codes are written as 8 hex digits for the address, then 8 for the value",
+            WarningKind::SkippedText { .. } => "cheat names and comments go before their codes",
+            WarningKind::DroppedEnableCode { .. } => "every device hooks into games differently, so use an enable code made for the output device",
+            WarningKind::CrcMismatch { .. } => "one of the cheat's lines is probably mistyped, missing or out of order",
+        };
+        format!("warning: {}\n{}", self, snippet(Some(self.span), source, hint))
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            WarningKind::SkippedToken { token, .. } =>
+                write!(f, "skipped \"{}\" at line {}", token, self.span.line),
+            WarningKind::HexAsText { token } =>
+                write!(f, "read \"{}\" at line {} as text instead of code", token, self.span.line),
+            WarningKind::SkippedText { text } =>
+                write!(f, "skipped \"{}\" at line {}, which isn't followed by any codes", text, self.span.line),
+            WarningKind::DroppedEnableCode { name } =>
+                write!(f, "dropped enable code \"{}\" at line {}", name, self.span.line),
+            WarningKind::CrcMismatch { name } =>
+                write!(f, "cheat \"{}\" at line {} doesn't match its CRC", name, self.span.line),
+        }
+    }
+}
+
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WarningKind::SkippedToken { token, .. } =>
+                write!(f, "unexpected \"{}\"", token),
+            WarningKind::HexAsText { token } =>
+                write!(f, "\"{}\" isn't a valid code", token),
+            WarningKind::SkippedText { text } =>
+                write!(f, "\"{}\" isn't followed by any codes", text),
+            WarningKind::DroppedEnableCode { name } =>
+                write!(f, "enable code \"{}\" can't be translated", name),
+            WarningKind::CrcMismatch { name } =>
+                write!(f, "cheat \"{}\" doesn't match its CRC", name),
+        }
+    }
+}
+
+// Quote the line of source text a span points to, with a caret under the span and a hint below
+fn snippet(span: Option<Span>, source: &str, hint: &str) -> String {
+    if let Some(span) = span {
+        if let Some(text) = source.lines().nth(span.line.wrapping_sub(1)) {
+            let number = span.line.to_string();
+            let gutter = " ".repeat(number.len());
+
+            let mut output = format!("{}--> {}:{}\n", gutter, span.line, span.column);
+            output += &format!("{} |\n", gutter);
+            output += &format!("{} | {}\n", number, text);
+            output += &format!("{} | {}{}\n", gutter, " ".repeat(span.column.saturating_sub(1)), "^".repeat(span.length.max(1)));
+            output += &format!("{} |\n", gutter);
+            output += &format!("{} = hint: {}\n", gutter, hint);
+            return output;
+        }
+    }
+
+    format!("  = hint: {}\n", hint)
+}
+
+impl ErrorKind {
+    // Suggestion on how to fix the input
+    pub fn hint(&self) -> &'static str {
+        match self {
+            ErrorKind::InvalidCharacter { .. } =>
+                "ARMAX codes use the digits 0-9 and the letters A-Z, except I, L, O and S",
+            ErrorKind::LineTooShort { .. } =>
+                "ARMAX lines have 13 characters, written as XXXX-XXXX-XXXXX",
+            ErrorKind::Parity { .. } =>
+                "one of the characters on this line is probably mistyped",
+            ErrorKind::BitString { .. } =>
+                "the cheat is probably missing some of its lines",
+            ErrorKind::InvalidHex =>
+                "codes are written as 8 hex digits for the address, then 8 for the value",
+            ErrorKind::MissingValue =>
+                "every code address needs a value",
+            ErrorKind::NoCodes =>
+                "cheats need at least one code line",
+            ErrorKind::Crc =>
+                "one of the cheat's lines is probably mistyped, missing or out of order",
+            ErrorKind::ControlValue(_) | ErrorKind::SeedValue(_) =>
+                "check the value of the preceding DEADFACE key line",
+            ErrorKind::Skipped(_) =>
+                "use the simple parser to skip over text that isn't part of a cheat",
+            ErrorKind::InvalidOption { .. } =>
+                "check the conversion options",
+            ErrorKind::UnknownCode(_) =>
+                "this code type isn't supported by the input device",
+            ErrorKind::Untranslatable(_) =>
+                "the output device can't do this, so the cheat needs to be rewritten by hand",
+            ErrorKind::Syntax { .. } =>
+                "instructions are written like `write32 0x003F38AB = 0x7F` or `if16 0x004A0000 == 0xFFFF then 2`",
+            ErrorKind::UnverifiedCipher(_) =>
+                "use the device's raw format until its encryption is supported",
+        }
+    }
+
+    // Whether the error is specific to ARMAX lines
+    fn is_armax(&self) -> bool {
+        matches!(self,
+            ErrorKind::InvalidCharacter { .. } |
+            ErrorKind::LineTooShort { .. } |
+            ErrorKind::Parity { .. } |
+            ErrorKind::BitString { .. })
+    }
+}
+
+// English ordinal of a number, e.g. "3rd"
+fn ordinal(n: usize) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Error::new(kind)
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::InvalidCharacter { character, index } =>
+                write!(f, "non-alphanumeric character \"{}\" in ARMAX code at index {}", character, index),
+            ErrorKind::LineTooShort { length } =>
+                write!(f, "ARMAX code of length {} is too short", length),
+            ErrorKind::Parity { .. } =>
+                write!(f, "parity mismatch"),
+            ErrorKind::BitString { length } =>
+                write!(f, "unable to read bit string of length {}", length),
+            ErrorKind::InvalidHex =>
+                write!(f, "invalid hex code"),
+            ErrorKind::MissingValue =>
+                write!(f, "code address is missing its value"),
+            ErrorKind::NoCodes =>
+                write!(f, "no codes to decrypt"),
+            ErrorKind::Crc =>
+                write!(f, "CRC mismatch"),
+            ErrorKind::ControlValue(c) =>
+                write!(f, "unrecognized AR2 control value {}", c),
+            ErrorKind::SeedValue(s) =>
+                write!(f, "AR2 seed {} is out of range", s),
+            ErrorKind::Skipped(kind) =>
+                write!(f, "{}", kind),
+            ErrorKind::InvalidOption { option, reason } =>
+                write!(f, "invalid {}: {}", option, reason),
+            ErrorKind::UnknownCode(code) =>
+                write!(f, "unrecognized code type {:08X}", code),
+            ErrorKind::Untranslatable(what) =>
+                write!(f, "{} can't be translated to the output device", what),
+            ErrorKind::Syntax { expected } =>
+                write!(f, "expected {}", expected),
+            ErrorKind::UnverifiedCipher(cipher) =>
+                write!(f, "unsupported {} encryption", cipher),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(line) = self.line {
+            let kind = if self.kind.is_armax() { "ARMAX line" } else { "line" };
+            write!(f, " in {} {}", ordinal(line + 1), kind)?;
+        }
+        if let Some(cheat) = &self.cheat {
+            write!(f, " of cheat '{}'", cheat)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
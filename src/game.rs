@@ -17,6 +17,12 @@ pub struct Game {
     pub region: Region,
 }
 
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Game {
     pub fn new() -> Self {
         Game {
@@ -0,0 +1,220 @@
+use crate::error::{Error, ErrorKind, Result};
+use crate::ir::{check_fits, Compare, Instruction, Skip, Width};
+
+// ARMAX address word layout: 2-bit subtype, 3-bit type, 2-bit size, then a 25-bit address
+// TODO: Verify fill & slide and the skip-2/skip-all conditional subtypes against published ARMAX codes. Only the
+// skip-1 conditional is covered by one, the Kingdom Hearts (USA) enable code.
+const ADDR_MASK: u32 = 0x01FFFFFF;
+
+// Value of the zero code ending a block of conditional lines
+const END_IF: u32 = 0x40000000;
+// Value nibble of the zero code starting a fill & slide
+const SLIDE: u32 = 0x8;
+
+fn subtype(addr: u32) -> u32 { addr >> 30 }
+fn kind(addr: u32) -> u32 { (addr >> 27) & 0x7 }
+
+fn width(addr: u32) -> Option<Width> {
+    match (addr >> 25) & 0x3 {
+        0 => Some(Width::Bits8),
+        1 => Some(Width::Bits16),
+        2 => Some(Width::Bits32),
+        _ => None,
+    }
+}
+
+fn size(width: Width) -> u32 {
+    match width {
+        Width::Bits8 => 0,
+        Width::Bits16 => 1,
+        Width::Bits32 => 2,
+    }
+}
+
+// Build an address word
+fn word(subtype: u32, kind: u32, width: Width, addr: u32) -> Result<u32> {
+    let addr = check_fits(addr, ADDR_MASK, "address above 0x01FFFFFF")?;
+    Ok((subtype << 30) | (kind << 27) | (size(width) << 25) | addr)
+}
+
+// Decode raw ARMAX codes without their verifier, each with the line it starts at
+// Remarks: Conditionals skip a number of code lines, not instructions
+pub fn decode(codes: &[u32]) -> Result<Vec<(usize, Instruction)>> {
+    let mut output: Vec<(usize, Instruction)> = vec!();
+    let lines: Vec<&[u32]> = codes.chunks_exact(2).collect();
+
+    let mut line = 0;
+    while line < lines.len() {
+        let (addr, val) = (lines[line][0], lines[line][1]);
+        let unknown = || Error::new(ErrorKind::UnknownCode(addr)).at_line(line);
+
+        // Zero codes: terminator, end of a conditional block, or fill & slide
+        if addr == 0 {
+            match val >> 28 {
+                _ if val == 0 => {
+                    line += 1;
+                    continue;
+                },
+                _ if val == END_IF => output.push((line, Instruction::EndIf)),
+                SLIDE => {
+                    // 00000000 8aaaaaaa / vvvvvvvv iinnssss - Write n values, moving s bytes and adding i each time
+                    let (value, ctrl) = lines.get(line + 1).map(|pair| (pair[0], pair[1]))
+                        .ok_or(Error::new(ErrorKind::MissingValue).at_line(line + 1))?;
+                    let width = width(val).ok_or_else(unknown)?;
+                    let multi = Instruction::MultiWrite {
+                        width,
+                        addr: val & ADDR_MASK,
+                        value: value & width.mask(),
+                        count: (ctrl >> 16) & 0xFF,
+                        stride: (ctrl & 0xFFFF) as i16 as u32,
+                        step: (ctrl >> 24) as i8 as u32,
+                    };
+                    output.push((line, multi));
+                    line += 1;
+                },
+                _ => return Err(unknown()),
+            }
+            line += 1;
+            continue;
+        }
+
+        let width = width(addr).ok_or_else(unknown)?;
+        let target = addr & ADDR_MASK;
+        let inst = match (kind(addr), subtype(addr)) {
+            (0, 0) => {
+                // Write, repeating 8-bit and 16-bit values over the bytes after them
+                let (value, count) = match width {
+                    Width::Bits32 => (val, 0),
+                    _ => (val & width.mask(), val >> (width.bytes() * 8)),
+                };
+                if count == 0 {
+                    Instruction::write(width, target, value)
+                } else {
+                    Instruction::MultiWrite { width, addr: target, value, count: count + 1, stride: width.bytes(), step: 0 }
+                }
+            },
+            (0, 1) => {
+                // Write to a pointer, with the offset above 8-bit and 16-bit values
+                let (value, offset) = match width {
+                    Width::Bits32 => (val, 0),
+                    _ => (val & width.mask(), (val >> (width.bytes() * 8)) * width.bytes()),
+                };
+                Instruction::PointerWrite { width, pointer: target, offset, value }
+            },
+            (0, 2) => Instruction::Increment { width, addr: target, value: val & width.mask() },
+            (0, _) => Instruction::Hook { addr: target, value: val },
+            (cmp, skip) => {
+                let cmp = match cmp {
+                    1 => Compare::Equal,
+                    2 => Compare::NotEqual,
+                    3 => Compare::LessSigned,
+                    4 => Compare::GreaterSigned,
+                    5 => Compare::Less,
+                    6 => Compare::Greater,
+                    _ => Compare::And,
+                };
+                let skip_lines = match skip {
+                    0 => Skip::Lines(1),
+                    1 => Skip::Lines(2),
+                    2 => Skip::All,
+                    _ => return Err(unknown()),
+                };
+                Instruction::If { cmp, width, addr: target, value: val & width.mask(), skip_lines }
+            },
+        };
+
+        output.push((line, inst));
+        line += 1;
+    }
+
+    Ok(output)
+}
+
+// Encode a single instruction into raw ARMAX codes
+// Remarks: Conditionals skip a number of code lines, not instructions
+pub fn encode(inst: &Instruction) -> Result<Vec<u32>> {
+    let output = match *inst {
+        Instruction::Write8 { addr, value } => {
+            vec!(word(0, 0, Width::Bits8, addr)?, value & 0xFF)
+        },
+        Instruction::Write16 { addr, value } => {
+            vec!(word(0, 0, Width::Bits16, addr)?, value & 0xFFFF)
+        },
+        Instruction::Write32 { addr, value } => {
+            vec!(word(0, 0, Width::Bits32, addr)?, value)
+        },
+        Instruction::Increment { width, addr, value } => {
+            vec!(word(2, 0, width, addr)?, value & width.mask())
+        },
+        Instruction::Decrement { width, addr, value } => {
+            vec!(word(2, 0, width, addr)?, value.wrapping_neg() & width.mask())
+        },
+        Instruction::MultiWrite { width, addr, value, count, stride, step } => {
+            if width != Width::Bits32 && stride == width.bytes() && step == 0 && count > 0 {
+                // Fill
+                let count = check_fits(count - 1, 0xFFFFFFFF >> (width.bytes() * 8), "multi-write count")?;
+                vec!(word(0, 0, width, addr)?, (count << (width.bytes() * 8)) | (value & width.mask()))
+            }
+            else {
+                // Fill & slide
+                let count = check_fits(count, 0xFF, "multi-write count")?;
+                if stride as i32 != stride as i16 as i32 {
+                    return Err(Error::new(ErrorKind::Untranslatable("multi-write stride")));
+                }
+                if step as i32 != step as i8 as i32 {
+                    return Err(Error::new(ErrorKind::Untranslatable("multi-write step")));
+                }
+                let ctrl = ((step & 0xFF) << 24) | (count << 16) | (stride & 0xFFFF);
+                vec!(0, (SLIDE << 28) | word(0, 0, width, addr)?, value & width.mask(), ctrl)
+            }
+        },
+        Instruction::CopyBytes { .. } => {
+            return Err(Error::new(ErrorKind::Untranslatable("byte copy")));
+        },
+        Instruction::PointerWrite { width, pointer, offset, value } => {
+            let value = value & width.mask();
+            let val = match width {
+                Width::Bits32 if offset == 0 => value,
+                Width::Bits32 => return Err(Error::new(ErrorKind::Untranslatable("32-bit pointer write with an offset"))),
+                _ => {
+                    if !offset.is_multiple_of(width.bytes()) {
+                        return Err(Error::new(ErrorKind::Untranslatable("unaligned pointer offset")));
+                    }
+                    let offset = check_fits(offset / width.bytes(), 0xFFFFFFFF >> (width.bytes() * 8), "pointer offset")?;
+                    (offset << (width.bytes() * 8)) | value
+                },
+            };
+            vec!(word(1, 0, width, pointer)?, val)
+        },
+        Instruction::If { cmp, width, addr, value, skip_lines } => {
+            let kind = match cmp {
+                Compare::Equal => 1,
+                Compare::NotEqual => 2,
+                Compare::LessSigned => 3,
+                Compare::GreaterSigned => 4,
+                Compare::Less => 5,
+                Compare::Greater => 6,
+                Compare::And => 7,
+            };
+            let subtype = match skip_lines {
+                Skip::Lines(0) => return Err(Error::new(ErrorKind::Untranslatable("conditional skipping no lines"))),
+                Skip::Lines(1) => 0,
+                Skip::Lines(2) => 1,
+                Skip::All => 2,
+                Skip::Lines(_) => return Err(Error::new(ErrorKind::Untranslatable("conditional skipping more than 2 lines"))),
+            };
+            vec!(word(subtype, kind, width, addr)?, value & width.mask())
+        },
+        Instruction::EndIf => {
+            vec!(0, END_IF)
+        },
+        Instruction::Hook { addr, value } => {
+            vec!(word(3, 0, Width::Bits32, addr)?, value)
+        },
+        Instruction::Joker { .. } => {
+            return encode(&inst.unjoker());
+        },
+    };
+
+    Ok(output)
+}
@@ -0,0 +1,191 @@
+pub mod armax;
+pub mod standard;
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::formats::CodeDevice;
+
+// Size of a memory access
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Width {
+    Bits8,
+    Bits16,
+    Bits32,
+}
+
+impl Width {
+    // Largest value the width can hold
+    pub fn mask(self) -> u32 {
+        match self {
+            Width::Bits8 => 0xFF,
+            Width::Bits16 => 0xFFFF,
+            Width::Bits32 => 0xFFFFFFFF,
+        }
+    }
+
+    pub fn bytes(self) -> u32 {
+        match self {
+            Width::Bits8 => 1,
+            Width::Bits16 => 2,
+            Width::Bits32 => 4,
+        }
+    }
+}
+
+// Comparison made by a conditional
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Compare {
+    Equal,
+    NotEqual,
+    Less,           // Unsigned
+    Greater,        // Unsigned
+    LessSigned,
+    GreaterSigned,
+    And,            // Any of the value's bits are set
+}
+
+// Instructions skipped when a conditional fails
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Skip {
+    Lines(usize),   // A number of following instructions, however many code lines they take up on a device
+    All,            // Everything up to the next EndIf, or the end of the cheat
+}
+
+// PS2 pad buttons, by their bit in the pad's 16-bit state
+pub const BUTTONS: [(u16, &str); 16] = [
+    (0x0001, "Select"),
+    (0x0002, "L3"),
+    (0x0004, "R3"),
+    (0x0008, "Start"),
+    (0x0010, "Up"),
+    (0x0020, "Right"),
+    (0x0040, "Down"),
+    (0x0080, "Left"),
+    (0x0100, "L2"),
+    (0x0200, "R2"),
+    (0x0400, "L1"),
+    (0x0800, "R1"),
+    (0x1000, "Triangle"),
+    (0x2000, "Circle"),
+    (0x4000, "Cross"),
+    (0x8000, "Square"),
+];
+
+// A single decrypted code operation, independent of the device it was written for
+#[derive(Clone, Debug, PartialEq)]
+pub enum Instruction {
+    Write8 { addr: u32, value: u32 },
+    Write16 { addr: u32, value: u32 },
+    Write32 { addr: u32, value: u32 },
+    Increment { width: Width, addr: u32, value: u32 },
+    Decrement { width: Width, addr: u32, value: u32 },
+    // Write count values, moving stride bytes and adding step to the value after each write
+    MultiWrite { width: Width, addr: u32, value: u32, count: u32, stride: u32, step: u32 },
+    CopyBytes { src: u32, dst: u32, count: u32 },
+    // Write to the address stored at pointer, plus offset
+    PointerWrite { width: Width, pointer: u32, offset: u32, value: u32 },
+    If { cmp: Compare, width: Width, addr: u32, value: u32, skip_lines: Skip },
+    // Run instructions only while the buttons are held, reading the pad's 16-bit state at addr
+    Joker { addr: u32, buttons: u16, skip_lines: Skip },
+    EndIf,
+    // Hook the cheat engine into the game. Only meaningful on the device the hook was made for.
+    Hook { addr: u32, value: u32 },
+}
+
+impl Instruction {
+    // Plain write of any width
+    pub fn write(width: Width, addr: u32, value: u32) -> Self {
+        match width {
+            Width::Bits8 => Instruction::Write8 { addr, value },
+            Width::Bits16 => Instruction::Write16 { addr, value },
+            Width::Bits32 => Instruction::Write32 { addr, value },
+        }
+    }
+
+    // Conditional a joker stands for. Pads hold one bit per button, cleared while the button is held.
+    pub fn unjoker(&self) -> Instruction {
+        match *self {
+            Instruction::Joker { addr, buttons, skip_lines } => {
+                Instruction::If { cmp: Compare::Equal, width: Width::Bits16, addr, value: (!buttons) as u32, skip_lines }
+            },
+            _ => self.clone(),
+        }
+    }
+}
+
+// Decode the decrypted codes of a device
+// Remarks: ARMAX codes are read without their verifier. Jokers are read as plain conditionals.
+pub fn decode(device: CodeDevice, codes: &[u32]) -> Result<Vec<Instruction>> {
+    Ok(decode_lines(device, codes)?.into_iter().map(|(_, inst)| inst).collect())
+}
+
+// Encode instructions into the decrypted codes of a device
+pub fn encode(device: CodeDevice, instructions: &[Instruction]) -> Result<Vec<u32>> {
+    let lines: Vec<(usize, Instruction)> = instructions.iter().cloned().enumerate().collect();
+    Ok(encode_lines(device, &lines)?.concat())
+}
+
+// Decode codes into instructions, each with the index of the code line it starts at
+pub fn decode_lines(device: CodeDevice, codes: &[u32]) -> Result<Vec<(usize, Instruction)>> {
+    if !codes.len().is_multiple_of(2) {
+        return Err(Error::new(ErrorKind::MissingValue).at_line(codes.len() / 2));
+    }
+
+    let mut output = match device {
+        CodeDevice::ARMAX => armax::decode(codes)?,
+        _ => standard::decode(codes, standard::table(device))?,
+    };
+
+    // Devices count conditional lines, so count the instructions starting within those lines instead
+    for i in 0..output.len() {
+        if let (line, Instruction::If { skip_lines: Skip::Lines(lines), .. }) = output[i] {
+            let end = line + 1 + lines;
+            let count = output[i+1..].iter().take_while(|(start, _)| *start < end).count();
+            if let Instruction::If { skip_lines, .. } = &mut output[i].1 {
+                *skip_lines = Skip::Lines(count);
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+// Encode instructions into the codes of each one, keeping errors at the line an instruction was read from
+pub fn encode_lines(device: CodeDevice, instructions: &[(usize, Instruction)]) -> Result<Vec<Vec<u32>>> {
+    let mut output: Vec<Vec<u32>> = vec![vec!(); instructions.len()];
+
+    // Work backwards, so conditionals know how many lines the instructions they skip take up
+    for i in (0..instructions.len()).rev() {
+        let (line, inst) = &instructions[i];
+        let inst = match inst.unjoker() {
+            Instruction::If { cmp, width, addr, value, skip_lines: Skip::Lines(count) } => {
+                // Conditionals need something to run, within the cheat
+                if count == 0 {
+                    return Err(Error::new(ErrorKind::Untranslatable("conditional with no instructions to run")).at_line(*line));
+                }
+                if count >= instructions.len() - i {
+                    return Err(Error::new(ErrorKind::Untranslatable("conditional running past the end of the cheat")).at_line(*line));
+                }
+                let lines = output[i+1..].iter().take(count).map(|codes| codes.len() / 2).sum();
+                Instruction::If { cmp, width, addr, value, skip_lines: Skip::Lines(lines) }
+            },
+            // Conditionals skipping everything run to the end of a cheat anyway
+            Instruction::EndIf if i == instructions.len() - 1 && device != CodeDevice::ARMAX => continue,
+            inst => inst,
+        };
+
+        output[i] = match device {
+            CodeDevice::ARMAX => armax::encode(&inst),
+            _ => standard::encode(&inst, standard::table(device)),
+        }.map_err(|e| e.at_line(*line))?;
+    }
+
+    Ok(output)
+}
+
+// Make sure a value fits in a number of bits, or fail with what couldn't be encoded
+fn check_fits(value: u32, mask: u32, what: &'static str) -> Result<u32> {
+    if value & !mask != 0 {
+        return Err(Error::new(ErrorKind::Untranslatable(what)));
+    }
+    Ok(value)
+}
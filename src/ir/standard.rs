@@ -0,0 +1,234 @@
+use crate::error::{Error, ErrorKind, Result};
+use crate::formats::CodeDevice;
+use crate::ir::{check_fits, Compare, Instruction, Skip, Width};
+
+// Address bits below the command nibble
+const ADDR_MASK: u32 = 0x0FFFFFFF;
+
+// Commands that differ between devices. Every other command is shared by every non-ARMAX device.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Table {
+    pub hook:           u32,    // Command nibble of hook codes
+    pub conditionals:   bool,   // Whether the C/D/E conditionals have the shared layout
+}
+
+// Get the commands that differ for a device
+// TODO: Add the CodeBreaker V7 boolean (7), delay (B) and master code (F) commands once checked against published codes
+// TODO: Check GS3 conditionals against published codes. Until then they're refused instead of guessed.
+pub fn table(device: CodeDevice) -> Table {
+    match device {
+        CodeDevice::CB => Table { hook: 0xF, conditionals: true },
+        CodeDevice::GS3 => Table { hook: 0xF, conditionals: false },
+        _ => Table { hook: 0x9, conditionals: true },
+    }
+}
+
+// Decode the standard code types, each with the line it starts at
+// Remarks: Conditionals skip a number of code lines, not instructions
+pub fn decode(codes: &[u32], table: Table) -> Result<Vec<(usize, Instruction)>> {
+    let mut output: Vec<(usize, Instruction)> = vec!();
+    let lines: Vec<&[u32]> = codes.chunks_exact(2).collect();
+
+    let mut line = 0;
+    while line < lines.len() {
+        let (addr, val) = (lines[line][0], lines[line][1]);
+        let cmd = addr >> 28;
+        let unknown = || Error::new(ErrorKind::UnknownCode(addr)).at_line(line);
+        // Second line of two-line codes
+        let next = || lines.get(line + 1).map(|pair| (pair[0], pair[1]))
+            .ok_or(Error::new(ErrorKind::MissingValue).at_line(line + 1));
+
+        let (inst, length) = match cmd {
+            0x0 => (Instruction::Write8 { addr: addr & ADDR_MASK, value: val & 0xFF }, 1),
+            0x1 => (Instruction::Write16 { addr: addr & ADDR_MASK, value: val & 0xFFFF }, 1),
+            0x2 => (Instruction::Write32 { addr: addr & ADDR_MASK, value: val }, 1),
+            0x3 => {
+                // 30t0vvvv 0aaaaaaa - Increment (even t) or decrement (odd t), with 32-bit values on the next line
+                let kind = (addr >> 20) & 0xF;
+                let (width, value, length) = match kind >> 1 {
+                    0 => (Width::Bits8, addr & 0xFF, 1),
+                    1 => (Width::Bits16, addr & 0xFFFF, 1),
+                    2 => (Width::Bits32, next()?.0, 2),
+                    _ => return Err(unknown()),
+                };
+                let addr = val & ADDR_MASK;
+                if kind & 1 == 0 {
+                    (Instruction::Increment { width, addr, value }, length)
+                } else {
+                    (Instruction::Decrement { width, addr, value }, length)
+                }
+            },
+            0x4 => {
+                // 4aaaaaaa nnnnssss / vvvvvvvv iiiiiiii - Write n values, moving s words between each
+                let (value, step) = next()?;
+                let multi = Instruction::MultiWrite {
+                    width: Width::Bits32,
+                    addr: addr & ADDR_MASK,
+                    value,
+                    count: val >> 16,
+                    stride: (val & 0xFFFF) * 4,
+                    step,
+                };
+                (multi, 2)
+            },
+            0x5 => {
+                // 5sssssss nnnnnnnn / 0ddddddd 00000000 - Copy n bytes from s to d
+                let (dst, _) = next()?;
+                (Instruction::CopyBytes { src: addr & ADDR_MASK, dst: dst & ADDR_MASK, count: val }, 2)
+            },
+            0x6 => {
+                // 6aaaaaaa vvvvvvvv / 000tnnnn iiiiiiii - Write v to the address at a, plus i
+                // TODO: Follow pointer chains longer than one level
+                let (kind, offset) = next()?;
+                let width = match kind >> 16 {
+                    0 => Width::Bits8,
+                    1 => Width::Bits16,
+                    2 => Width::Bits32,
+                    _ => return Err(unknown()),
+                };
+                if kind & 0xFFFF != 1 {
+                    return Err(unknown());
+                }
+                (Instruction::PointerWrite { width, pointer: addr & ADDR_MASK, offset, value: val & width.mask() }, 2)
+            },
+            0xC if table.conditionals => {
+                // Caaaaaaa vvvvvvvv - Run every following line if equal
+                let cond = Instruction::If { cmp: Compare::Equal, width: Width::Bits32, addr: addr & ADDR_MASK, value: val, skip_lines: Skip::All };
+                (cond, 1)
+            },
+            0xD if table.conditionals => {
+                // Daaaaaaa 00c0vvvv - 16-bit, or Daaaaaaa 00c100vv - 8-bit. Run the next line if true.
+                let width = if val & 0x10000 == 0 { Width::Bits16 } else { Width::Bits8 };
+                let cmp = decode_compare((val >> 20) & 0xF).ok_or_else(unknown)?;
+                (Instruction::If { cmp, width, addr: addr & ADDR_MASK, value: val & width.mask(), skip_lines: Skip::Lines(1) }, 1)
+            },
+            0xE if table.conditionals => {
+                // E0nnvvvv caaaaaaa - 16-bit, or E1nn00vv caaaaaaa - 8-bit. Run the next n lines if true.
+                let width = match (addr >> 24) & 0xF {
+                    0 => Width::Bits16,
+                    1 => Width::Bits8,
+                    _ => return Err(unknown()),
+                };
+                let cmp = decode_compare(val >> 28).ok_or_else(unknown)?;
+                let lines = ((addr >> 16) & 0xFF) as usize;
+                (Instruction::If { cmp, width, addr: val & ADDR_MASK, value: addr & width.mask(), skip_lines: Skip::Lines(lines) }, 1)
+            },
+            _ if cmd == table.hook => (Instruction::Hook { addr: addr & ADDR_MASK, value: val }, 1),
+            _ => return Err(unknown()),
+        };
+
+        output.push((line, inst));
+        line += length;
+    }
+
+    Ok(output)
+}
+
+// Encode a single instruction into standard code types
+// Remarks: Conditionals skip a number of code lines, not instructions
+pub fn encode(inst: &Instruction, table: Table) -> Result<Vec<u32>> {
+    if matches!(inst, Instruction::If { .. } | Instruction::EndIf) && !table.conditionals {
+        return Err(Error::new(ErrorKind::Untranslatable("conditional")));
+    }
+
+    let output = match *inst {
+        Instruction::Write8 { addr, value } => {
+            vec!(check_address(addr)?, value & 0xFF)
+        },
+        Instruction::Write16 { addr, value } => {
+            vec!(0x10000000 | check_address(addr)?, value & 0xFFFF)
+        },
+        Instruction::Write32 { addr, value } => {
+            vec!(0x20000000 | check_address(addr)?, value)
+        },
+        Instruction::Increment { width, addr, value } | Instruction::Decrement { width, addr, value } => {
+            let kind = match width {
+                Width::Bits8 => 0x0,
+                Width::Bits16 => 0x2,
+                Width::Bits32 => 0x4,
+            } | matches!(inst, Instruction::Decrement { .. }) as u32;
+            let addr = check_address(addr)?;
+            match width {
+                Width::Bits32 => vec!(0x30000000 | (kind << 20), addr, value, 0),
+                _ => vec!(0x30000000 | (kind << 20) | (value & width.mask()), addr),
+            }
+        },
+        Instruction::MultiWrite { width, addr, value, count, stride, step } => {
+            if width != Width::Bits32 || !stride.is_multiple_of(4) {
+                return Err(Error::new(ErrorKind::Untranslatable("8-bit and 16-bit multi-write")));
+            }
+            let count = check_fits(count, 0xFFFF, "multi-write count")?;
+            let stride = check_fits(stride / 4, 0xFFFF, "multi-write stride")?;
+            vec!(0x40000000 | check_address(addr)?, (count << 16) | stride, value, step)
+        },
+        Instruction::CopyBytes { src, dst, count } => {
+            vec!(0x50000000 | check_address(src)?, count, check_address(dst)?, 0)
+        },
+        Instruction::PointerWrite { width, pointer, offset, value } => {
+            let kind = match width {
+                Width::Bits8 => 0,
+                Width::Bits16 => 1,
+                Width::Bits32 => 2,
+            };
+            vec!(0x60000000 | check_address(pointer)?, value & width.mask(), (kind << 16) | 1, offset)
+        },
+        Instruction::If { cmp: Compare::Equal, width: Width::Bits32, addr, value, skip_lines: Skip::All } => {
+            vec!(0xC0000000 | check_address(addr)?, value)
+        },
+        Instruction::If { cmp, width, addr, value, skip_lines: Skip::Lines(lines) } => {
+            let cmp = encode_compare(cmp)?;
+            let value = value & width.mask();
+            let addr = check_address(addr)?;
+            match (width, lines) {
+                (Width::Bits16, 1) => vec!(0xD0000000 | addr, (cmp << 20) | value),
+                (Width::Bits8, 1) => vec!(0xD0000000 | addr, (cmp << 20) | 0x10000 | value),
+                (Width::Bits16, _) => vec!(0xE0000000 | (check_lines(lines)? << 16) | value, (cmp << 28) | addr),
+                (Width::Bits8, _) => vec!(0xE1000000 | (check_lines(lines)? << 16) | value, (cmp << 28) | addr),
+                (Width::Bits32, _) => return Err(Error::new(ErrorKind::Untranslatable("32-bit conditional"))),
+            }
+        },
+        Instruction::If { .. } => {
+            return Err(Error::new(ErrorKind::Untranslatable("conditional skipping every following line")));
+        },
+        Instruction::EndIf => {
+            return Err(Error::new(ErrorKind::Untranslatable("end of conditional block")));
+        },
+        Instruction::Hook { addr, value } => {
+            vec!((table.hook << 28) | check_address(addr)?, value)
+        },
+        Instruction::Joker { .. } => {
+            return encode(&inst.unjoker(), table);
+        },
+    };
+
+    Ok(output)
+}
+
+// Standard conditional comparison nibbles
+fn decode_compare(nibble: u32) -> Option<Compare> {
+    match nibble {
+        0 => Some(Compare::Equal),
+        1 => Some(Compare::NotEqual),
+        2 => Some(Compare::Less),
+        3 => Some(Compare::Greater),
+        _ => None,
+    }
+}
+
+fn encode_compare(cmp: Compare) -> Result<u32> {
+    match cmp {
+        Compare::Equal => Ok(0),
+        Compare::NotEqual => Ok(1),
+        Compare::Less => Ok(2),
+        Compare::Greater => Ok(3),
+        _ => Err(Error::new(ErrorKind::Untranslatable("signed or bitwise comparison"))),
+    }
+}
+
+fn check_address(addr: u32) -> Result<u32> {
+    check_fits(addr, ADDR_MASK, "address above 0x0FFFFFFF")
+}
+
+fn check_lines(lines: usize) -> Result<u32> {
+    check_fits(lines as u32, 0xFF, "conditional skipping more than 255 lines")
+}
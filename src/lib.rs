@@ -1,18 +1,35 @@
-mod ar2;
-mod armax;
-mod cheat;
-mod formats;
-mod game;
+pub mod ar1;
+pub mod ar2;
+pub mod armax;
+pub mod asm;
+pub mod cheat;
+pub mod detect;
+pub mod disasm;
+pub mod error;
+pub mod formats;
+pub mod game;
+pub mod ir;
 mod magic;
-mod omniconvert;
-mod token;
+pub mod omniconvert;
+pub mod token;
+pub mod translate;
+
+pub use error::{Error, Result, Warning};
+pub use detect::{detect_format, Guess};
+pub use omniconvert::{compile, convert, ConversionOutput, ConvertOptions};
 
 #[cfg(test)]
 mod armax_tests {
+    use crate::ar2;
     use crate::armax;
+    use crate::armax::verifier::Expansion;
     use crate::cheat::Cheat;
+    use crate::error::{ErrorKind, WarningKind};
+    use crate::formats::{CodeFormat, FORMATS};
     use crate::game::{Game, Region};
     use crate::omniconvert;
+    use crate::omniconvert::ConvertOptions;
+    use crate::token::Span;
 
     // "Enable Code" for Kingdom Hearts (USA)
     const TEST_CHEAT_SINGLE: &str =
@@ -61,7 +78,12 @@ Save Anywhere
 
     #[test]
     fn armax_decode_single() {
-        assert_eq!(Some(vec!((3589363552 as u32, 1721823442 as u32))), armax::decrypt::alpha_to_octets(vec!("UQRNER36M3RD5")))
+        assert_eq!(Ok(vec!((3589363552 as u32, 1721823442 as u32))), armax::decrypt::alpha_to_octets(vec!("UQRNER36M3RD5")))
+    }
+
+    #[test]
+    fn armax_encode_single() {
+        assert_eq!(vec!("UQRN-ER36-M3RD5".to_string()), armax::encrypt::octets_to_alpha(&[(3589363552, 1721823442)]))
     }
 
     // Part of decrypt_pair()
@@ -77,7 +99,7 @@ Save Anywhere
         let tokens = omniconvert::read_input(TEST_CHEAT_SINGLE, state.incrypt.code.format);
 
         // Parse input into cheats
-        game.cheats = omniconvert::build_cheat_list(tokens);
+        game.cheats = omniconvert::build_cheat_list(tokens, &mut vec!()).unwrap();
 
         assert!(game.cheats.len() > 0);
 
@@ -113,7 +135,7 @@ Save Anywhere
         let tokens = omniconvert::read_input(TEST_CHEAT_SINGLE, state.incrypt.code.format);
 
         // Parse input into cheats
-        game.cheats = omniconvert::build_cheat_list(tokens);
+        game.cheats = omniconvert::build_cheat_list(tokens, &mut vec!()).unwrap();
 
         assert!(game.cheats.len() > 0);
 
@@ -164,7 +186,7 @@ Save Anywhere
         let tokens = omniconvert::read_input(TEST_CHEAT_SINGLE, state.incrypt.code.format);
 
         // Parse input into cheats
-        game.cheats = omniconvert::build_cheat_list(tokens);
+        game.cheats = omniconvert::build_cheat_list(tokens, &mut vec!()).unwrap();
 
         assert!(game.cheats.len() > 0);
 
@@ -221,7 +243,7 @@ Save Anywhere
         let tokens = omniconvert::read_input(TEST_CHEAT_SINGLE, state.incrypt.code.format);
 
         // Parse input into cheats
-        game.cheats = omniconvert::build_cheat_list(tokens);
+        game.cheats = omniconvert::build_cheat_list(tokens, &mut vec!()).unwrap();
 
         assert!(game.cheats.len() > 0);
 
@@ -244,23 +266,98 @@ Save Anywhere
 
     }
 
+    // Encrypt single ActionReplay MAX octet pair
+    #[test]
+    fn armax_encrypt_single_pair() {
+        // Default state
+        let state: omniconvert::State = omniconvert::State::new();
+        let seeds = &state.armax_seeds;
+
+        // Re-encrypt the first decrypted pair of the enable code
+        let (out_addr, out_val) = armax::encrypt::encrypt_pair((2169439932, 678980011), seeds);
+
+        assert_eq!(out_addr, 3589363552);
+        assert_eq!(out_val, 1721823442);
+
+        // Encode back to the original line
+        assert_eq!(armax::encrypt::octets_to_alpha(&[(out_addr, out_val)]), vec!("UQRN-ER36-M3RD5".to_string()));
+    }
+
+    // Encrypt a decrypted ActionReplay MAX cheat and compare to the original lines
+    #[test]
+    fn armax_encrypt_cheat() {
+        // Default state
+        let mut state: omniconvert::State = omniconvert::State::new();
+
+        // Tokenize input
+        let tokens = omniconvert::read_input(TEST_CHEAT_SINGLE, state.incrypt.code.format);
+
+        // Parse input into cheats
+        let encrypted = omniconvert::build_cheat_list(tokens, &mut vec!()).unwrap().remove(0);
+
+        // Decrypt, then encrypt again
+        let decrypted = armax::decrypt::decrypt_cheat(encrypted.clone(), &state.armax_seeds, &mut state.ar2_input).unwrap();
+        let reencrypted = armax::encrypt::encrypt_cheat(decrypted, &state.armax_seeds, &mut state.ar2_output).unwrap();
+
+        assert_eq!(reencrypted.codes, encrypted.codes);
+
+        // Encode back to text
+        let pairs: Vec<(u32, u32)> = reencrypted.codes.chunks(2).map(|pair| (pair[0], pair[1])).collect();
+        assert_eq!(armax::encrypt::octets_to_alpha(&pairs).join("\n"), TEST_CHEAT_SINGLE);
+    }
+
+    // Carry AR2 key changes from one ActionReplay MAX cheat to the next, as on a whole AR2 game
+    #[test]
+    fn armax_ar2_key_change() {
+        let mut state: omniconvert::State = omniconvert::State::new();
+        let game: Game = Game {
+            id: 0x029E,
+            name: "Kingdom Hearts".to_string(),
+            cheats: vec![],
+            region: Region::USA
+        };
+
+        // Raw cheats, the 1st one changing the AR2 key
+        let mut keyed = Cheat::new();
+        keyed.codes = vec!(ar2::KEY_ADDR, 0x00000102, 0x003F38AB, 0x0000007F);
+        let mut next = Cheat::new();
+        next.codes = vec!(0x104A0000, 0x0000FFFF);
+        let cheats = [keyed, next].map(|raw| armax::verifier::add_verifier(&raw, &game, &[]).unwrap());
+
+        let encrypted: Vec<Cheat> = cheats.iter()
+            .map(|cheat| armax::encrypt::encrypt_cheat(cheat.clone(), &state.armax_seeds, &mut state.ar2_output).unwrap())
+            .collect();
+        assert_ne!(state.ar2_output, state.ar2_seeds);
+
+        // The 2nd cheat doesn't decrypt from the default seeds
+        let decrypted = armax::decrypt::decrypt_cheat(encrypted[1].clone(), &state.armax_seeds, &mut state.ar2_seeds.clone());
+        assert_ne!(decrypted.map(|cheat| cheat.codes).ok(), Some(cheats[1].codes.clone()));
+
+        // Key lines are dropped when decrypting
+        let decrypted = armax::decrypt::decrypt_cheat(encrypted[0].clone(), &state.armax_seeds, &mut state.ar2_input).unwrap();
+        assert_eq!(decrypted.codes, [&cheats[0].codes[..2], &cheats[0].codes[4..]].concat());
+        let decrypted = armax::decrypt::decrypt_cheat(encrypted[1].clone(), &state.armax_seeds, &mut state.ar2_input).unwrap();
+        assert_eq!(decrypted.codes, cheats[1].codes);
+        assert_eq!(state.ar2_input, state.ar2_output);
+    }
+
     // Decrypt single ActionReplay MAX cheat
     #[test]
     fn armax_decrypt_cheat() {
         // Default state
-        let state: omniconvert::State = omniconvert::State::new();
+        let mut state: omniconvert::State = omniconvert::State::new();
 
         // Tokenize input
         let tokens = omniconvert::read_input(TEST_CHEAT_SINGLE, state.incrypt.code.format);
 
         // Parse input into cheats
-        let encrypted_cheats: Vec<Cheat> = omniconvert::build_cheat_list(tokens);
+        let encrypted_cheats: Vec<Cheat> = omniconvert::build_cheat_list(tokens, &mut vec!()).unwrap();
 
         // Decrypt
         let decrypted_cheats: Vec<Cheat> = encrypted_cheats
             .into_iter()
             .map(|cheat| {
-                armax::decrypt::decrypt_cheat(cheat, &state.armax_seeds, &state.ar2_seeds)
+                armax::decrypt::decrypt_cheat(cheat, &state.armax_seeds, &mut state.ar2_input).unwrap()
             })
             .collect();
 
@@ -269,6 +366,7 @@ Save Anywhere
         assert_eq!(decrypted_cheats[0].codes.len(), 10);
 
         // Cheat metadata
+        assert_eq!(decrypted_cheats[0].crc_valid, Some(true));
         assert_eq!(decrypted_cheats[0].enable_code, true);
         assert_eq!(decrypted_cheats[0].game_id, 0x029E);
 
@@ -279,11 +377,346 @@ Save Anywhere
         assert_eq!(decrypted_cheats[0].codes, vec!(0x014F06BC, 0x287869AB, 0x74680000, 0x00000000, 0xC411F668, 0x00000800, 0x0C0F0094, 0x00000001, 0xC4000000, 0x00010801))
     }
 
+    // Rebuild ActionReplay MAX verifiers from decrypted cheat metadata
+    #[test]
+    fn armax_make_verifier() {
+        // Default state
+        let mut state: omniconvert::State = omniconvert::State::new();
+
+        // Tokenize input
+        let tokens = omniconvert::read_input(TEST_CHEAT_MULTIPLE, state.incrypt.code.format);
+
+        // Parse and decrypt input
+        let cheats: Vec<Cheat> = omniconvert::build_cheat_list(tokens, &mut vec!()).unwrap()
+            .into_iter()
+            .map(|cheat| {
+                armax::decrypt::decrypt_cheat(cheat, &state.armax_seeds, &mut state.ar2_input).unwrap()
+            })
+            .collect();
+
+        // Enable code carries a disc hash expansion
+        let verifier = armax::verifier::make_verifier(&cheats[0], &[Expansion { kind: 7, data: 0x869AB746 }]).unwrap();
+        assert_eq!(verifier, cheats[0].codes.split_at(4).0);
+        assert_eq!(armax::decrypt::read_verifier_length(&verifier).unwrap(), 2);
+
+        // Regular cheats have a single verifier line
+        for cheat in &cheats[1..] {
+            let verifier = armax::verifier::make_verifier(cheat, &[]).unwrap();
+            assert_eq!(verifier, cheat.codes.split_at(2).0);
+            assert_eq!(armax::decrypt::read_verifier_length(&verifier).unwrap(), 1);
+        }
+
+        // Bits that don't fit are an error instead of a panic
+        let mut output = [0u32; 2];
+        let mut key: [u32; 3] = [1, 31, 2];
+        let error = armax::verifier::write_bit_string(&mut output, &mut key, 0b11, 2).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::BitString { length: 2 });
+    }
+
+    // Build an ActionReplay MAX cheat from raw codes and a game
+    #[test]
+    fn armax_add_verifier() {
+        let game: Game = Game {
+            id: 0x029E,
+            name: "Kingdom Hearts".to_string(),
+            cheats: vec![],
+            region: Region::USA
+        };
+
+        // Raw "Have All Trinities" code
+        let mut raw = Cheat::new();
+        raw.id = 0x6BC5;
+        raw.name = "Have All Trinities".to_string();
+        raw.codes = vec!(0x003F38AB, 0x0000007F);
+
+        let cheat = armax::verifier::add_verifier(&raw, &game, &[]).unwrap();
+
+        assert_eq!(cheat.game_id, 0x029E);
+        assert_eq!(cheat.region, 0);
+        assert_eq!(cheat.name, "Have All Trinities");
+        assert_eq!(cheat.codes, vec!(0x014F06BC, 0x50800000, 0x003F38AB, 0x0000007F));
+    }
+
+    // Reject malformed ARMAX lines instead of panicking
+    #[test]
+    fn armax_decode_errors() {
+        // Last character mistyped, failing the parity check
+        let error = armax::decrypt::alpha_to_octets(vec!("UQRNER36M3RD4")).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Parity { octets: (3589363552, 1721823442) });
+        assert_eq!(error.line, Some(0));
+
+        // Character outside the cipher alphabet
+        let error = armax::decrypt::alpha_to_octets(vec!("UQRNIR36M3RD5")).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::InvalidCharacter { character: 'I', index: 4 });
+
+        // Missing characters
+        let error = armax::decrypt::alpha_to_octets(vec!("UQRNER36")).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::LineTooShort { length: 8 });
+    }
+
+    // Report the cheat and line of a malformed ARMAX line
+    #[test]
+    fn armax_parse_error_location() {
+        // Default state
+        let state: omniconvert::State = omniconvert::State::new();
+
+        // "Enable Code" with a mistyped 2nd line
+        let tokens = omniconvert::read_input("Enable Code\nUQRN-ER36-M3RD5\nWC60-T93N-MGJBX", state.incrypt.code.format);
+
+        let error = omniconvert::build_cheat_list(tokens, &mut vec!()).unwrap_err();
+        assert!(matches!(error.kind, ErrorKind::Parity { .. }));
+        assert_eq!(error.cheat, Some("Enable Code".to_string()));
+        assert_eq!(error.line, Some(1));
+    }
+
+    // Keep the source span of each token
+    #[test]
+    fn armax_token_spans() {
+        // Default state
+        let state: omniconvert::State = omniconvert::State::new();
+
+        let tokens = omniconvert::read_input(TEST_CHEAT_MULTIPLE, state.incrypt.code.format);
+
+        let last = tokens.last().unwrap();
+        assert_eq!(last.string, "3WQR-X7EE-ADTJA");
+        assert_eq!(last.span, Span { line: 15, column: 1, length: 15 });
+
+        let name = tokens.iter().find(|t| t.string == "Anywhere").unwrap();
+        assert_eq!(name.span, Span { line: 12, column: 6, length: 8 });
+
+        // Cheats keep the span of each code line
+        let cheats = omniconvert::build_cheat_list(tokens, &mut vec!()).unwrap();
+        assert_eq!(cheats[2].spans, vec!(Span { line: 14, column: 1, length: 15 }, Span { line: 15, column: 1, length: 15 }));
+    }
+
+    // Render parse errors with the offending line and a caret under it
+    #[test]
+    fn armax_render_parse_error() {
+        // Default state
+        let state: omniconvert::State = omniconvert::State::new();
+
+        // "Save Anywhere" with a mistyped last character
+        let input = "Save Anywhere\n3QYW-CWCU-R0BCC\n3WQR-X7EE-ADTJB";
+        let tokens = omniconvert::read_input(input, state.incrypt.code.format);
+
+        let error = omniconvert::build_cheat_list(tokens, &mut vec!()).unwrap_err();
+        assert_eq!(error.to_string(), "parity mismatch in 2nd ARMAX line of cheat 'Save Anywhere'");
+        assert_eq!(error.render(input),
+"error: parity mismatch in 2nd ARMAX line of cheat 'Save Anywhere'
+ --> 3:1
+  |
+3 | 3WQR-X7EE-ADTJB
+  | ^^^^^^^^^^^^^^^
+  |
+  = hint: one of the characters on this line is probably mistyped
+");
+
+        // Caret only under an invalid character
+        let input = "Save Anywhere\n3QYW-CWCU-R0BCC\n  3WQR-X7EI-ADTJA";
+        let tokens = omniconvert::read_input(input, state.incrypt.code.format);
+
+        let error = omniconvert::build_cheat_list(tokens, &mut vec!()).unwrap_err();
+        assert_eq!(error.span, Some(Span { line: 3, column: 11, length: 1 }));
+        assert!(error.render(input).contains("3 |   3WQR-X7EI-ADTJA\n  |           ^\n"));
+    }
+
+    // Point decryption errors at the line they were read from
+    #[test]
+    fn armax_render_decrypt_error() {
+        // Default state
+        let mut state: omniconvert::State = omniconvert::State::new();
+
+        // "Enable Code" missing all but its 1st line
+        let input = "Enable Code\nUQRN-ER36-M3RD5";
+        let tokens = omniconvert::read_input(input, state.incrypt.code.format);
+        let cheat = omniconvert::build_cheat_list(tokens, &mut vec!()).unwrap().remove(0);
+
+        let error = omniconvert::decrypt_cheat(&mut state, CodeFormat::ARMAX, cheat).unwrap_err();
+        assert_eq!(error.span, Some(Span { line: 2, column: 1, length: 15 }));
+        assert!(error.render(input).starts_with("error: unable to read bit string of length 32 in 1st ARMAX line of cheat 'Enable Code'\n --> 2:1\n"));
+    }
+
+    // Reject ARMAX cheats that can't be decrypted
+    #[test]
+    fn armax_decrypt_errors() {
+        // Default state
+        let mut state: omniconvert::State = omniconvert::State::new();
+
+        // Nothing to decrypt
+        let empty = Cheat::new();
+        let error = armax::decrypt::decrypt_cheat(empty, &state.armax_seeds, &mut state.ar2_input).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::NoCodes);
+        assert_eq!(error.cheat, Some("New Cheat".to_string()));
+
+        // Trailing address without a value
+        let mut trailing = Cheat::new();
+        trailing.codes = vec!(3589363552, 1721823442, 3589363552);
+        let error = armax::decrypt::decrypt_cheat(trailing, &state.armax_seeds, &mut state.ar2_input).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::MissingValue);
+        assert_eq!(error.line, Some(1));
+
+        // Verifier running past the end of the cheat
+        let mut truncated = Cheat::new();
+        truncated.codes = vec!(3589363552, 1721823442);
+        let error = armax::decrypt::decrypt_cheat(truncated, &state.armax_seeds, &mut state.ar2_input).unwrap_err();
+        assert!(matches!(error.kind, ErrorKind::BitString { .. }));
+    }
+
+    // Convert ActionReplay MAX cheats to raw codes through the public API
+    #[test]
+    fn armax_convert_to_raw() {
+        let output = crate::convert(TEST_CHEAT_MULTIPLE, &FORMATS[8], &FORMATS[0], &ConvertOptions::default()).unwrap();
+
+        assert_eq!(output.game.id, 0x029E);
+        assert_eq!(output.game.region, Region::USA);
+        assert_eq!(output.game.cheats.len(), 2);
+
+        // The 32-bit conditional in this enable code has no raw equivalent
+        assert_eq!(output.warnings.len(), 1);
+        assert_eq!(output.warnings[0].kind, WarningKind::DroppedEnableCode { name: "Enable Code".to_string() });
+        assert_eq!(output.warnings[0].span.line, 5);
+
+        // Verifiers are dropped, and codes are translated
+        assert_eq!(output.text,
+"Have All Trinities
+003F38AB 0000007F
+
+Save Anywhere
+# Press Pause to access the menu
+204865E0 00114288");
+    }
+
+    // Translate enable codes that only hook into the game
+    #[test]
+    fn armax_convert_enable_code() {
+        let options = ConvertOptions { game_id: Some(0x029E), ..Default::default() };
+        let armax = crate::convert("Enable Code\n9011F668 0C0A9225", &FORMATS[2], &FORMATS[8], &options).unwrap();
+        assert!(armax.game.cheats[0].enable_code);
+
+        let output = crate::convert(&armax.text, &FORMATS[8], &FORMATS[3], &ConvertOptions::default()).unwrap();
+        assert!(output.warnings.is_empty());
+        assert_eq!(output.text, "Enable Code\nF011F668 0C0A9225");
+    }
+
+    // Give every cheat converted to ARMAX its own code ID, under a game ID that has to be given
+    #[test]
+    fn armax_convert_code_ids() {
+        let raw = "Have All Trinities\n003F38AB 0000007F\n\nSave Anywhere\n204865E0 00114288";
+        let error = crate::convert(raw, &FORMATS[2], &FORMATS[8], &ConvertOptions::default()).err().unwrap();
+        assert!(matches!(error.kind, ErrorKind::InvalidOption { option: "game ID", .. }));
+
+        let options = ConvertOptions { game_id: Some(0x029E), ..Default::default() };
+        let armax = crate::convert(raw, &FORMATS[2], &FORMATS[8], &options).unwrap();
+
+        // Read back from the verifiers
+        let output = crate::convert(&armax.text, &FORMATS[8], &FORMATS[8], &ConvertOptions::default()).unwrap();
+        assert_eq!(output.game.cheats.iter().map(|cheat| cheat.id).collect::<Vec<u32>>(), vec!(1, 2));
+        assert_eq!(output.game.cheats.iter().map(|cheat| cheat.game_id).collect::<Vec<u32>>(), vec!(0x029E, 0x029E));
+    }
+
+    // Convert ActionReplay MAX cheats to themselves, keeping their verifiers
+    #[test]
+    fn armax_convert_round_trip() {
+        let output = crate::convert(TEST_CHEAT_MULTIPLE, &FORMATS[8], &FORMATS[8], &ConvertOptions::default()).unwrap();
+
+        assert_eq!(output.text, TEST_CHEAT_MULTIPLE);
+    }
+
+    // Surface errors from the public API
+    #[test]
+    fn armax_convert_error() {
+        let error = crate::convert("Enable Code\nUQRN-ER36-M3RD4", &FORMATS[8], &FORMATS[0], &ConvertOptions::default()).err().unwrap();
+
+        assert_eq!(error.to_string(), "parity mismatch in 1st ARMAX line of cheat 'Enable Code'");
+    }
+
+    // Validate conversion options before reading any input
+    #[test]
+    fn armax_convert_options() {
+        // Manual verifiers only make sense for ARMAX output
+        let options = ConvertOptions { verifier: armax::VerifierMode::Manual, ..Default::default() };
+        let error = crate::convert(TEST_CHEAT_MULTIPLE, &FORMATS[8], &FORMATS[0], &options).err().unwrap();
+        assert!(matches!(error.kind, ErrorKind::InvalidOption { option: "verifier mode", .. }));
+
+        let mut state = omniconvert::State::new();
+        state.set_output(&FORMATS[8]).unwrap();
+        state.set_verifier_mode(armax::VerifierMode::Manual).unwrap();
+        assert!(state.set_output(&FORMATS[0]).is_err());
+        assert!(state.set_disc_hash(Some(0x1234)).is_err());
+        assert!(state.output() == &FORMATS[8]);
+
+        // Forced region replaces the one read from the cheats
+        let options = ConvertOptions { region: Region::PAL, ..Default::default() };
+        let output = crate::convert(TEST_CHEAT_MULTIPLE, &FORMATS[8], &FORMATS[0], &options).unwrap();
+        assert_eq!(output.game.region, Region::PAL);
+
+        // Strict parsing rejects text that isn't part of a cheat
+        let options = ConvertOptions { parser: omniconvert::ParserType::Strict, ..Default::default() };
+        let error = crate::convert("Infinite Health 003F38AB\n003F38AB 0000007F", &FORMATS[0], &FORMATS[0], &options).err().unwrap();
+        assert!(matches!(error.kind, ErrorKind::Skipped(WarningKind::HexAsText { .. })));
+        assert_eq!(error.span.map(|s| s.line), Some(1));
+    }
+
+    // Detect a mistyped character that still passes the parity check
+    #[test]
+    fn armax_crc_mismatch() {
+        // Default state
+        let mut state: omniconvert::State = omniconvert::State::new();
+
+        // "Have All Trinities" with 'P' mistyped as 'N'
+        let tokens = omniconvert::read_input("NMGE-KJ9D-X4WRN\nQJNC-EWMH-UQ48H", state.incrypt.code.format);
+
+        let cheat = armax::decrypt::decrypt_cheat(omniconvert::build_cheat_list(tokens, &mut vec!()).unwrap().remove(0), &state.armax_seeds, &mut state.ar2_input).unwrap();
+
+        assert_eq!(cheat.crc_valid, Some(false));
+
+        // Conversions warn about it, or stop when parsing strictly
+        let input = "Have All Trinities\nNMGE-KJ9D-X4WRN\nQJNC-EWMH-UQ48H";
+        let output = crate::convert(input, &FORMATS[8], &FORMATS[0], &ConvertOptions::default()).unwrap();
+        assert_eq!(output.warnings[0].kind, WarningKind::CrcMismatch { name: "Have All Trinities".to_string() });
+        assert_eq!(output.warnings[0].span.line, 2);
+
+        let options = ConvertOptions { parser: omniconvert::ParserType::Strict, ..Default::default() };
+        let error = crate::convert(input, &FORMATS[8], &FORMATS[0], &options).err().unwrap();
+        assert_eq!(error.kind, ErrorKind::Crc);
+        assert_eq!(error.to_string(), "CRC mismatch of cheat 'Have All Trinities'");
+
+        // Only ARMAX codes carry a CRC
+        let cheat = omniconvert::build_cheat_list(omniconvert::read_input("003F38AB 0000007F", CodeFormat::RAW), &mut vec!()).unwrap();
+        assert_eq!(cheat[0].crc_valid, None);
+    }
+
+    // Embed a disc hash in an enable code's verifier
+    #[test]
+    fn armax_disc_hash_verifier() {
+        let game: Game = Game {
+            id: 0x029E,
+            name: "Kingdom Hearts".to_string(),
+            cheats: vec![],
+            region: Region::USA
+        };
+
+        // Raw "Enable Code"
+        let mut raw = Cheat::new();
+        raw.id = 0x6BC2;
+        raw.enable_code = true;
+        raw.codes = vec!(0xC411F668, 0x00000800, 0x0C0F0094, 0x00000001, 0xC4000000, 0x00010801);
+
+        let expansions = armax::verifier::expansions_for(&raw, Some(0x869AB746));
+        let cheat = armax::verifier::add_verifier(&raw, &game, &expansions).unwrap();
+
+        assert_eq!(cheat.codes, vec!(0x014F06BC, 0x287869AB, 0x74680000, 0x00000000, 0xC411F668, 0x00000800, 0x0C0F0094, 0x00000001, 0xC4000000, 0x00010801));
+
+        // Regular cheats ignore the disc hash
+        raw.enable_code = false;
+        assert_eq!(armax::verifier::expansions_for(&raw, Some(0x869AB746)), vec!());
+    }
+
     // Decrypt multiple ActionReplay MAX cheats
     #[test]
     fn armax_decrypt_game() {
         // Default state
-        let state: omniconvert::State = omniconvert::State::new();
+        let mut state: omniconvert::State = omniconvert::State::new();
 
         // Dummy game object
         let mut game: Game = Game {
@@ -297,10 +730,10 @@ Save Anywhere
         let tokens = omniconvert::read_input(TEST_CHEAT_MULTIPLE, state.incrypt.code.format);
 
         // Build list of encrypted cheats, then decrypt and add to game
-        game.cheats = omniconvert::build_cheat_list(tokens)
+        game.cheats = omniconvert::build_cheat_list(tokens, &mut vec!()).unwrap()
             .into_iter()
             .map(|cheat| {
-                armax::decrypt::decrypt_cheat(cheat, &state.armax_seeds, &state.ar2_seeds)
+                armax::decrypt::decrypt_cheat(cheat, &state.armax_seeds, &mut state.ar2_input).unwrap()
             })
             .collect();
 
@@ -319,6 +752,7 @@ Save Anywhere
         assert_eq!(game.cheats.len(), 3);
 
         // Cheat codes
+        assert_eq!(game.cheats[0].crc_valid, Some(true));             // CRC16 check
         assert_eq!(game.cheats[0].enable_code, true);           // Enable code flag
         assert_eq!(game.cheats[0].name, "Enable Code");         // Cheat name
         assert_eq!(                                             // Verifier
@@ -326,6 +760,7 @@ Save Anywhere
             vec!(0x014F06BC, 0x287869AB, 0x74680000, 0x00000000)
         );
         assert_eq!(game.cheats[0].codes, vec!(0x014F06BC, 0x287869AB, 0x74680000, 0x00000000, 0xC411F668, 0x00000800, 0x0C0F0094, 0x00000001, 0xC4000000, 0x00010801));
+        assert_eq!(game.cheats[1].crc_valid, Some(true));             // CRC16 check
         assert_eq!(game.cheats[1].enable_code, false);          // Enable code flag
         assert_eq!(game.cheats[1].name, "Have All Trinities");  // Cheat name
         assert_eq!(                                             // Verifier
@@ -333,9 +768,10 @@ Save Anywhere
             vec!(0x014F06BC, 0x50800000)
         );
         assert_eq!(game.cheats[1].codes, vec!(0x014F06BC, 0x50800000, 0x003F38AB, 0x0000007F));
+        assert_eq!(game.cheats[2].crc_valid, Some(true));             // CRC16 check
         assert_eq!(game.cheats[2].enable_code, false);          // Enable code flag
         assert_eq!(game.cheats[2].name, "Save Anywhere");       // Cheat name
-        //assert_eq!(game.cheats[2].comment, "Press pause to access the menu");
+        assert_eq!(game.cheats[2].comment, "Press Pause to access the menu");
         assert_eq!(                                             // Verifier
             game.cheats[2].codes.split_at(2).0,
             vec!(0x014F06BC, 0x60800000)
@@ -345,4 +781,801 @@ Save Anywhere
     }
 }
 
+#[cfg(test)]
+mod cb_tests {
+    use crate::error::ErrorKind;
+    use crate::formats::FORMATS;
+    use crate::omniconvert::{self, ConvertOptions};
+
+    // Refuse conversions to and from CodeBreaker V1-V6, whose cipher isn't implemented
+    #[test]
+    fn cb1_unverified() {
+        let error = crate::convert("Infinite Health\n003F38AB 0000007F", &FORMATS[3], &FORMATS[9], &ConvertOptions::default()).err().unwrap();
+        assert_eq!(error.kind, ErrorKind::UnverifiedCipher("CodeBreaker V1-V6"));
+        assert_eq!(error.to_string(), "unsupported CodeBreaker V1-V6 encryption");
+
+        // Refused when chosen, before reading any input
+        let mut state = omniconvert::State::new();
+        assert_eq!(state.set_input(&FORMATS[9]).err().map(|e| e.kind), Some(ErrorKind::UnverifiedCipher("CodeBreaker V1-V6")));
+        assert_eq!(state.set_output(&FORMATS[9]).err().map(|e| e.kind), Some(ErrorKind::UnverifiedCipher("CodeBreaker V1-V6")));
+        assert!(state.input() == &FORMATS[8] && state.output() == &FORMATS[0]);
+    }
+
+    // Refuse conversions to and from CodeBreaker V7, whose cipher isn't implemented
+    #[test]
+    fn cb7_unverified() {
+        let error = crate::convert("Infinite Health\n003F38AB 0000007F", &FORMATS[3], &FORMATS[10], &ConvertOptions::default()).err().unwrap();
+        assert_eq!(error.kind, ErrorKind::UnverifiedCipher("CodeBreaker V7"));
+
+        let error = crate::convert("Infinite Health\nBEEFC0DE 00000000\n2AF09C1B 9D3E5C07", &FORMATS[10], &FORMATS[3], &ConvertOptions::default()).err().unwrap();
+        assert_eq!(error.kind, ErrorKind::UnverifiedCipher("CodeBreaker V7"));
+    }
+}
+
+#[cfg(test)]
+mod gs3_tests {
+    use crate::error::ErrorKind;
+    use crate::formats::FORMATS;
+    use crate::omniconvert::ConvertOptions;
+
+    // Refuse conversions to and from GS3 and GS5, whose cipher isn't implemented
+    #[test]
+    fn gs3_unverified() {
+        for code in [&FORMATS[13], &FORMATS[14]] {
+            let error = crate::convert("Infinite Health\n003F38AB 0000007F", &FORMATS[5], code, &ConvertOptions::default()).err().unwrap();
+            assert_eq!(error.kind, ErrorKind::UnverifiedCipher("GameShark V3+"));
+
+            let error = crate::convert("Infinite Health\n003F38AB 0000007F", code, &FORMATS[5], &ConvertOptions::default()).err().unwrap();
+            assert_eq!(error.kind, ErrorKind::UnverifiedCipher("GameShark V3+"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod ar2_tests {
+    use crate::ar2;
+    use crate::cheat::Cheat;
+    use crate::error::ErrorKind;
+    use crate::formats::FORMATS;
+    use crate::game::Game;
+    use crate::omniconvert::ConvertOptions;
+
+    // Raw "Have All Trinities" and "Save Anywhere" codes for Kingdom Hearts (USA), plus a 16-bit constant write
+    const TEST_CODES_RAW: [u32; 6] = [0x003F38AB, 0x0000007F, 0x044865E0, 0x00114288, 0x104A0000, 0x0000FFFF];
+
+    // Round trip every control value
+    #[test]
+    fn ar2_encrypt_decrypt_code() {
+        for ctrl in 0..8u8 {
+            for seed in [0u8, 3, 9, 30, 31] {
+                for code in TEST_CODES_RAW {
+                    let encrypted = ar2::encrypt::encrypt_code(code, ctrl, seed).unwrap();
+                    assert_eq!(ar2::decrypt::decrypt_code(encrypted, ctrl, seed).unwrap(), code);
+                }
+            }
+        }
+    }
+
+    // Round trip a list of codes with the default seeds
+    #[test]
+    fn ar2_encrypt_decrypt_cheat() {
+        let seeds = ar2::seeds::generate();
+
+        let encrypted = ar2::encrypt::encrypt_cheat(TEST_CODES_RAW.to_vec(), &mut seeds.clone()).unwrap();
+        assert_ne!(encrypted, TEST_CODES_RAW.to_vec());
+
+        assert_eq!(ar2::decrypt::decrypt_cheat(encrypted, &mut seeds.clone()).unwrap(), TEST_CODES_RAW.to_vec());
+    }
+
+    // Emit a DEADFACE line switching to a chosen seed key
+    #[test]
+    fn ar2_encrypt_rekeyed() {
+        let seeds = ar2::seeds::generate();
+        let key = 0x01020304;
+
+        let mut encrypt_seeds = seeds;
+        let encrypted = ar2::encrypt::encrypt_cheat_rekeyed(TEST_CODES_RAW.to_vec(), &mut encrypt_seeds, key).unwrap();
+        assert_eq!(encrypted.len(), TEST_CODES_RAW.len() + 2);
+
+        // Key line uses the default seeds
+        assert_eq!(ar2::decrypt::decrypt_code(encrypted[0], seeds[0], seeds[1]).unwrap(), 0xDEADFACE);
+        assert_eq!(ar2::decrypt::decrypt_code(encrypted[1], seeds[2], seeds[3]).unwrap(), key);
+
+        // Following lines use the new seeds
+        let new_seeds = ar2::seeds::regenerate(key);
+        assert_eq!(encrypt_seeds, new_seeds);
+        assert_eq!(encrypted.split_at(2).1, ar2::encrypt::encrypt_cheat(TEST_CODES_RAW.to_vec(), &mut new_seeds.clone()).unwrap());
+    }
+
+    // Re-key partway through a list and drop the key line
+    #[test]
+    fn ar2_decrypt_key_change() {
+        let seeds = ar2::seeds::generate();
+
+        // Key line in the middle of the list
+        let mut raw = TEST_CODES_RAW.to_vec();
+        raw.splice(2..2, [0xDEADFACE, 0x01020304]);
+        let encrypted = ar2::encrypt::encrypt_cheat(raw, &mut seeds.clone()).unwrap();
 
+        let mut decrypt_seeds = seeds;
+        assert_eq!(ar2::decrypt::decrypt_cheat(encrypted, &mut decrypt_seeds).unwrap(), TEST_CODES_RAW.to_vec());
+        assert_eq!(decrypt_seeds, ar2::seeds::regenerate(0x01020304));
+
+        // Key line as the very first line
+        let encrypted = ar2::encrypt::encrypt_cheat_rekeyed(TEST_CODES_RAW.to_vec(), &mut seeds.clone(), 0x01020304).unwrap();
+        assert_eq!(ar2::decrypt::decrypt_cheat(encrypted, &mut seeds.clone()).unwrap(), TEST_CODES_RAW.to_vec());
+
+        // Key line alone
+        let encrypted = ar2::encrypt::encrypt_cheat(vec!(0xDEADFACE, 0x01020304), &mut seeds.clone()).unwrap();
+        assert_eq!(ar2::decrypt::decrypt_cheat(encrypted, &mut seeds.clone()).unwrap(), vec!());
+    }
+
+    // Carry seed changes over from one cheat to the next
+    #[test]
+    fn ar2_decrypt_game() {
+        let seeds = ar2::seeds::generate();
+
+        let mut first = Cheat::new();
+        first.codes = vec!(0xDEADFACE, 0x01020304, 0x003F38AB, 0x0000007F);
+        let mut second = Cheat::new();
+        second.codes = vec!(0x044865E0, 0x00114288);
+
+        let mut game = Game::new();
+        game.cheats = vec!(first, second);
+
+        let encrypted = ar2::encrypt::encrypt_game(game, &seeds).unwrap();
+
+        // 2nd cheat is encrypted with the key from the 1st
+        assert_eq!(encrypted.cheats[1].codes, ar2::encrypt::encrypt_cheat(vec!(0x044865E0, 0x00114288), &mut ar2::seeds::regenerate(0x01020304)).unwrap());
+
+        let decrypted = ar2::decrypt::decrypt_game(encrypted, &seeds).unwrap();
+        assert_eq!(decrypted.cheats[0].codes, vec!(0x003F38AB, 0x0000007F));
+        assert_eq!(decrypted.cheats[1].codes, vec!(0x044865E0, 0x00114288));
+    }
+
+    // Reject keys giving seeds outside of the seed tables
+    #[test]
+    fn ar2_invalid_key() {
+        let seeds = ar2::seeds::generate();
+
+        // Control value 8 for addresses
+        let error = ar2::encrypt::encrypt_cheat(vec!(0xDEADFACE, 0x08000000, 0x003F38AB, 0x0000007F), &mut seeds.clone()).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::ControlValue(8));
+        assert_eq!(error.line, Some(1));
+
+        // Seed 64 for addresses
+        let mut encrypted = ar2::encrypt::encrypt_cheat(vec!(0xDEADFACE, 0x00400000), &mut seeds.clone()).unwrap();
+        encrypted.extend([0x003F38AB, 0x0000007F]);
+        let error = ar2::decrypt::decrypt_cheat(encrypted, &mut seeds.clone()).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::SeedValue(64));
+        assert_eq!(error.line, Some(1));
+    }
+
+    // Convert to AR2 with a chosen seed key, then back
+    #[test]
+    fn ar2_convert_rekeyed() {
+        let raw = "Have All Trinities\n003F38AB 0000007F\n\nSave Anywhere\n044865E0 00114288";
+        let options = ConvertOptions { ar2_key: Some(0x01020304), ..Default::default() };
+        let converted = crate::convert(raw, &FORMATS[2], &FORMATS[7], &options).unwrap();
+
+        // Only the first cheat switches keys, with the default seeds
+        let seeds = ar2::seeds::generate();
+        let first = &converted.game.cheats[0].codes;
+        assert_eq!(first.len(), 4);
+        assert_eq!(ar2::decrypt::decrypt_code(first[0], seeds[0], seeds[1]).unwrap(), 0xDEADFACE);
+        assert_eq!(ar2::decrypt::decrypt_code(first[1], seeds[2], seeds[3]).unwrap(), 0x01020304);
+        assert_eq!(converted.game.cheats[1].codes.len(), 2);
+
+        // Decrypting follows the key change and drops the key line
+        let decrypted = crate::convert(&converted.text, &FORMATS[7], &FORMATS[2], &ConvertOptions::default()).unwrap();
+        assert_eq!(decrypted.text, raw);
+
+        // Keys need AR2 output, and seeds inside the seed tables
+        let error = crate::convert(raw, &FORMATS[2], &FORMATS[6], &options).err().unwrap();
+        assert_eq!(error.kind, ErrorKind::InvalidOption { option: "AR2 key", reason: "AR2 keys need AR2 output" });
+        let options = ConvertOptions { ar2_key: Some(0x08000000), ..Default::default() };
+        let error = crate::convert(raw, &FORMATS[2], &FORMATS[7], &options).err().unwrap();
+        assert_eq!(error.kind, ErrorKind::InvalidOption { option: "AR2 key", reason: "its seeds are out of range" });
+    }
+}
+
+#[cfg(test)]
+mod ar1_tests {
+    use crate::ar1;
+    use crate::cheat::Cheat;
+    use crate::formats::CodeFormat;
+    use crate::omniconvert;
+
+    // Raw "Have All Trinities" and "Save Anywhere" codes for Kingdom Hearts (USA), plus a 16-bit constant write
+    const TEST_CODES_RAW: [u32; 6] = [0x003F38AB, 0x0000007F, 0x044865E0, 0x00114288, 0x104A0000, 0x0000FFFF];
+
+    // Round trip a whole AR1 cheat
+    #[test]
+    fn ar1_encrypt_decrypt_cheat() {
+        let mut cheat = Cheat::new();
+        cheat.codes = TEST_CODES_RAW.to_vec();
+
+        let encrypted = ar1::encrypt::encrypt_cheat(cheat).unwrap();
+        assert_ne!(encrypted.codes, TEST_CODES_RAW.to_vec());
+
+        let decrypted = ar1::decrypt::decrypt_cheat(encrypted).unwrap();
+        assert_eq!(decrypted.codes, TEST_CODES_RAW.to_vec());
+    }
+
+    // AR1 has no key changes, so DEADFACE lines are plain codes
+    #[test]
+    fn ar1_ignore_key_lines() {
+        let mut cheat = Cheat::new();
+        cheat.codes = vec!(0xDEADFACE, 0x01020304, 0x003F38AB, 0x0000007F);
+
+        let encrypted = ar1::encrypt::encrypt_cheat(cheat.clone()).unwrap();
+        let mut tail = Cheat::new();
+        tail.codes = vec!(0x003F38AB, 0x0000007F);
+        assert_eq!(encrypted.codes.split_at(2).1, ar1::encrypt::encrypt_cheat(tail).unwrap().codes);
+
+        assert_eq!(ar1::decrypt::decrypt_cheat(encrypted).unwrap().codes, cheat.codes);
+    }
+
+    // Convert AR1 codes to every other format and back
+    #[test]
+    fn ar1_convert_formats() {
+        let mut cheat = Cheat::new();
+        cheat.codes = TEST_CODES_RAW.to_vec();
+        let ar1_codes = ar1::encrypt::encrypt_cheat(cheat).unwrap().codes;
+
+        for format in [CodeFormat::AR2, CodeFormat::RAW] {
+            let mut state: omniconvert::State = omniconvert::State::new();
+
+            let mut input = Cheat::new();
+            input.codes = ar1_codes.clone();
+
+            // AR1 to other format
+            let raw = omniconvert::decrypt_cheat(&mut state, CodeFormat::AR1, input).unwrap();
+            let converted = omniconvert::encrypt_cheat(&mut state, format, raw).unwrap();
+
+            // Other format back to AR1
+            let raw = omniconvert::decrypt_cheat(&mut state, format, converted).unwrap();
+            assert_eq!(raw.codes, TEST_CODES_RAW.to_vec());
+            let output = omniconvert::encrypt_cheat(&mut state, CodeFormat::AR1, raw).unwrap();
+            assert_eq!(output.codes, ar1_codes);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod parser_tests {
+    use crate::cheat::Cheat;
+    use crate::error::{ErrorKind, WarningKind};
+    use crate::formats::{CodeFormat, FORMATS};
+    use crate::game::Game;
+    use crate::omniconvert;
+    use crate::token::Span;
+
+    // Collect warnings about hex octets read as text instead of printing them
+    #[test]
+    fn parser_hex_as_text_warning() {
+        let input = "Infinite Health 003F38AB";
+        let tokens = omniconvert::read_input(input, CodeFormat::RAW);
+
+        let mut warnings = vec!();
+        let cheats = omniconvert::build_cheat_list(tokens, &mut warnings).unwrap();
+
+        assert!(cheats.is_empty());
+
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].kind, WarningKind::HexAsText { token: "003F38AB".to_string() });
+        assert_eq!(warnings[0].span, Span { line: 1, column: 17, length: 8 });
+        assert!(warnings[0].render(input).starts_with("warning: read \"003F38AB\" at line 1 as text instead of code\n --> 1:17\n"));
+
+        // The cheat has no codes, so its text is skipped as well
+        assert_eq!(warnings[1].kind, WarningKind::SkippedText { text: "Infinite Health 003F38AB".to_string() });
+        assert_eq!(warnings[1].span, Span { line: 1, column: 1, length: 8 });
+    }
+
+    // Read raw address/value pairs, however they're separated
+    #[test]
+    fn parser_raw_codes() {
+        let input = "Have All Trinities
+003F38AB 0000007F
+0x003f38ac 0x0000007e
+003F38AD:0000007D
+003F38AE-0000007C
+003F38AF0000007B";
+
+        let tokens = omniconvert::read_input(input, CodeFormat::RAW);
+        let mut warnings = vec!();
+        let cheats = omniconvert::build_cheat_list(tokens, &mut warnings).unwrap();
+
+        assert!(warnings.is_empty());
+        assert_eq!(cheats.len(), 1);
+        assert_eq!(cheats[0].name, "Have All Trinities");
+        assert_eq!(cheats[0].codes, vec!(
+            0x003F38AB, 0x0000007F,
+            0x003F38AC, 0x0000007E,
+            0x003F38AD, 0x0000007D,
+            0x003F38AE, 0x0000007C,
+            0x003F38AF, 0x0000007B,
+        ));
+        assert_eq!(cheats[0].spans[2], Span { line: 4, column: 1, length: 17 });
+    }
+
+    // Reject raw codes with the wrong number of digits where they were read
+    #[test]
+    fn parser_raw_code_errors() {
+        let read = |input: &str| omniconvert::build_cheat_list(omniconvert::read_input(input, CodeFormat::RAW), &mut vec!()).err().unwrap();
+
+        let error = read("Infinite Health\n003F38AB 0000007F\n003F38A 0000007F");
+        assert_eq!(error.kind, ErrorKind::InvalidHex);
+        assert_eq!(error.cheat.as_deref(), Some("Infinite Health"));
+        assert_eq!(error.line, Some(1));
+        assert_eq!(error.span, Some(Span { line: 3, column: 1, length: 7 }));
+
+        let error = read("Infinite Health\n003F38AB:000007F");
+        assert_eq!(error.kind, ErrorKind::InvalidHex);
+        assert_eq!(error.span, Some(Span { line: 2, column: 10, length: 7 }));
+
+        let error = read("Infinite Health\n003F38AB000007F");
+        assert_eq!(error.kind, ErrorKind::InvalidHex);
+        assert_eq!(error.span, Some(Span { line: 2, column: 1, length: 15 }));
+
+        let error = read("Infinite Health\n003F38AB");
+        assert_eq!(error.kind, ErrorKind::MissingValue);
+        assert_eq!(error.span, Some(Span { line: 2, column: 1, length: 8 }));
+    }
+
+    // Read names made of hex words as names, where a name goes
+    #[test]
+    fn parser_hex_names() {
+        for name in ["Effaced Decade", "12345678", "# Kingdom Hearts\n12345678"] {
+            let input = format!("{}\n003F38AB 0000007F", name);
+            let mut warnings = vec!();
+            let cheats = omniconvert::build_cheat_list(omniconvert::read_input(&input, CodeFormat::RAW), &mut warnings).unwrap();
+
+            assert!(warnings.is_empty());
+            assert_eq!(cheats.len(), 1);
+            assert_eq!(cheats[0].name, name.lines().last().unwrap());
+            assert_eq!(cheats[0].codes, vec!(0x003F38AB, 0x0000007F));
+        }
+    }
+
+    // Round-trip cheat names and comments through parsing and writing
+    #[test]
+    fn parser_write_round_trip() {
+        let input = "Enable Code
+UQRN-ER36-M3RD5
+WC60-T93N-MGJBW
+7QTG-QEQB-YXP60
+VFE7-FK9B-M32EA
+KQEK-5ZFB-F8UP9
+
+Save Anywhere
+# Press Pause to access the menu
+# Then choose Save
+3QYW-CWCU-R0BCC
+3WQR-X7EE-ADTJA";
+
+        let tokens = omniconvert::read_input(input, CodeFormat::ARMAX);
+        let mut game = Game::new();
+        game.cheats = omniconvert::build_cheat_list(tokens, &mut vec!()).unwrap();
+
+        assert_eq!(game.cheats[1].name, "Save Anywhere");
+        assert_eq!(game.cheats[1].comment, "Press Pause to access the menu\nThen choose Save");
+        assert_eq!(omniconvert::write_output(&game, &FORMATS[8]), input);
+    }
+
+    // Write comments so they read back as comments, even when they look like codes or are empty
+    #[test]
+    fn parser_write_comments() {
+        let input = "Have All Trinities\n# 203F38AB 0000007F\n#\n# Open the menu first\n003F38AB 0000007F";
+
+        let tokens = omniconvert::read_input(input, CodeFormat::RAW);
+        let mut game = Game::new();
+        game.cheats = omniconvert::build_cheat_list(tokens, &mut vec!()).unwrap();
+        assert_eq!(game.cheats.len(), 1);
+        assert_eq!(game.cheats[0].comment, "203F38AB 0000007F\n\nOpen the menu first");
+        assert_eq!(game.cheats[0].codes, vec!(0x003F38AB, 0x0000007F));
+
+        let output = omniconvert::write_output(&game, &FORMATS[0]);
+        assert_eq!(output, input);
+        let tokens = omniconvert::read_input(&output, CodeFormat::RAW);
+        let cheats = omniconvert::build_cheat_list(tokens, &mut vec!()).unwrap();
+        assert_eq!(cheats.len(), 1);
+        assert_eq!((&cheats[0].comment, &cheats[0].codes), (&game.cheats[0].comment, &game.cheats[0].codes));
+    }
+
+    // Write codes as address/value pairs for everything but ARMAX
+    #[test]
+    fn parser_write_pairs() {
+        let mut cheat = Cheat::new();
+        cheat.name = "Have All Trinities".to_string();
+        cheat.comment = "Open the menu first".to_string();
+        cheat.codes = vec!(0x003F38AB, 0x0000007F);
+
+        for code in [&FORMATS[0], &FORMATS[9], &FORMATS[14]] {
+            assert_eq!(omniconvert::write_cheat(&cheat, code), "Have All Trinities\n# Open the menu first\n003F38AB 0000007F");
+        }
+    }
+
+    // Read comment lines and comments after codes, in any of the supported styles
+    #[test]
+    fn parser_comments() {
+        let input = "# Kingdom Hearts
+Have All Trinities
+// Needs the enable code
+PMGE-KJ9D-X4WRN ; first half
+QJNC-EWMH-UQ48H
+
+Save Anywhere
+3QYW-CWCU-R0BCC
+3WQR-X7EE-ADTJA # Press Pause to access the menu";
+
+        let tokens = omniconvert::read_input(input, CodeFormat::ARMAX);
+        let mut warnings = vec!();
+        let cheats = omniconvert::build_cheat_list(tokens, &mut warnings).unwrap();
+
+        assert!(warnings.is_empty());
+        assert_eq!(cheats.len(), 2);
+        assert_eq!(cheats[0].name, "Have All Trinities");
+        assert_eq!(cheats[0].comment, "Kingdom Hearts\nNeeds the enable code\nfirst half");
+        assert_eq!(cheats[0].codes.len(), 4);
+        assert_eq!(cheats[1].name, "Save Anywhere");
+        assert_eq!(cheats[1].comment, "Press Pause to access the menu");
+        assert_eq!(cheats[1].codes.len(), 4);
+    }
+}
+
+
+
+
+#[cfg(test)]
+mod translate_tests {
+    use crate::cheat::{Cheat, CheatStates};
+    use crate::error::ErrorKind;
+    use crate::formats::CodeDevice;
+    use crate::ir::{self, Compare, Instruction, Skip, Width};
+    use crate::translate;
+
+    // Translate standard codes to ARMAX and back
+    #[test]
+    fn translate_standard_armax_round_trip() {
+        let mut cheat = Cheat::new();
+        cheat.codes = vec!(
+            0x003F38AB, 0x0000007F,     // 8-bit write
+            0xD04A0000, 0x0000FFFF,     // 16-bit equal, for the next line
+            0x204865E0, 0x00114288,     // 32-bit write
+            0x30400000, 0x004A0010,     // 32-bit increment
+            0x00000005, 0x00000000,
+        );
+
+        let armax = translate::translate_cheat(&cheat, CodeDevice::STD, CodeDevice::ARMAX).unwrap();
+        assert_eq!(armax.state, CheatStates::Translated);
+        assert_eq!(armax.codes, vec!(
+            0x003F38AB, 0x0000007F,
+            0x0A4A0000, 0x0000FFFF,
+            0x044865E0, 0x00114288,
+            0x844A0010, 0x00000005,
+        ));
+
+        let standard = translate::translate_cheat(&armax, CodeDevice::ARMAX, CodeDevice::STD).unwrap();
+        assert_eq!(standard.codes, cheat.codes);
+    }
+
+    // Count conditional lines by the operations they cover, which can take up different numbers of lines
+    #[test]
+    fn translate_conditional_lines() {
+        let codes = vec!(
+            0xE0020001, 0x004A0000,     // 16-bit equal, for the next 2 lines
+            0x30400000, 0x004A0010,     // 32-bit increment, taking up 2 lines
+            0x00000005, 0x00000000,
+            0x003F38AB, 0x0000007F,
+        );
+
+        let instructions = ir::decode(CodeDevice::STD, &codes).unwrap();
+        assert_eq!(instructions, vec!(
+            Instruction::If { cmp: Compare::Equal, width: Width::Bits16, addr: 0x004A0000, value: 1, skip_lines: Skip::Lines(1) },
+            Instruction::Increment { width: Width::Bits32, addr: 0x004A0010, value: 5 },
+            Instruction::Write8 { addr: 0x003F38AB, value: 0x7F },
+        ));
+
+        // The conditional covers a single ARMAX line
+        let armax = ir::encode(CodeDevice::ARMAX, &instructions).unwrap();
+        assert_eq!(&armax[0..2], &[0x0A4A0000, 0x00000001]);
+        assert_eq!(ir::encode(CodeDevice::STD, &instructions).unwrap(), codes);
+
+        // Conditionals with nothing to run, or running past the end of the cheat
+        for (skip, what) in [(0, "conditional with no instructions to run"), (3, "conditional running past the end of the cheat")] {
+            let mut instructions = instructions.clone();
+            if let Instruction::If { skip_lines, .. } = &mut instructions[0] {
+                *skip_lines = Skip::Lines(skip);
+            }
+            for device in [CodeDevice::ARMAX, CodeDevice::STD] {
+                let error = ir::encode(device, &instructions).unwrap_err();
+                assert_eq!((error.kind, error.line), (ErrorKind::Untranslatable(what), Some(0)));
+            }
+        }
+
+        // A conditional decoded at the end of a cheat has nothing to run
+        let instructions = ir::decode_lines(CodeDevice::STD, &[0xD04A0000, 0x0000FFFF]).unwrap();
+        assert!(ir::encode_lines(CodeDevice::ARMAX, &instructions).is_err());
+    }
+
+    // Round trip the decrypted "Enable Code" for Kingdom Hearts (USA), a published ARMAX code with a conditional
+    #[test]
+    fn ir_armax_known_code() {
+        let codes = vec!(0xC411F668, 0x00000800, 0x0C0F0094, 0x00000001, 0xC4000000, 0x00010801);
+        let instructions = ir::decode(CodeDevice::ARMAX, &codes).unwrap();
+        assert_eq!(instructions[1], Instruction::If { cmp: Compare::Equal, width: Width::Bits32, addr: 0x000F0094, value: 1, skip_lines: Skip::Lines(1) });
+        assert_eq!(ir::encode(CodeDevice::ARMAX, &instructions).unwrap(), codes);
+    }
+
+    // Hooks are numbered differently on every device
+    #[test]
+    fn translate_hook() {
+        let mut cheat = Cheat::new();
+        cheat.codes = vec!(0x9011F668, 0x0C0A9225);
+
+        let cb = translate::translate_cheat(&cheat, CodeDevice::AR2, CodeDevice::CB).unwrap();
+        assert_eq!(cb.codes, vec!(0xF011F668, 0x0C0A9225));
+        assert!(cb.enable_code);
+    }
+
+    // Fail on operations the output device doesn't have
+    #[test]
+    fn translate_errors() {
+        let mut cheat = Cheat::new();
+        cheat.codes = vec!(
+            0x003F38AB, 0x0000007F,
+            0x5049C000, 0x00000010,     // Copy 16 bytes
+            0x0049D000, 0x00000000,
+        );
+
+        let error = translate::translate_cheat(&cheat, CodeDevice::STD, CodeDevice::ARMAX).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Untranslatable("byte copy"));
+        assert_eq!(error.line, Some(1));
+        assert_eq!(error.cheat, Some("New Cheat".to_string()));
+
+        cheat.codes = vec!(0x803F38AB, 0x0000007F);
+        let error = translate::translate_cheat(&cheat, CodeDevice::STD, CodeDevice::ARMAX).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::UnknownCode(0x803F38AB));
+        assert_eq!(error.line, Some(0));
+    }
+
+    // Only translate the commands known to match on each device
+    #[test]
+    fn translate_device_tables() {
+        let mut cheat = Cheat::new();
+        cheat.codes = vec!(
+            0xD04A0000, 0x0000FFFF,     // If equal, run the next line
+            0x104A0000, 0x0000FFFF,
+        );
+
+        let cb = translate::translate_cheat(&cheat, CodeDevice::AR2, CodeDevice::CB).unwrap();
+        assert_eq!(cb.codes, cheat.codes);
+
+        // GS3 conditionals haven't been checked against published codes
+        let error = translate::translate_cheat(&cheat, CodeDevice::AR2, CodeDevice::GS3).unwrap_err();
+        assert_eq!((error.kind, error.line), (ErrorKind::Untranslatable("conditional"), Some(0)));
+        let error = translate::translate_cheat(&cheat, CodeDevice::GS3, CodeDevice::AR2).unwrap_err();
+        assert_eq!((error.kind, error.line), (ErrorKind::UnknownCode(0xD04A0000), Some(0)));
+    }
+
+    // Encode jokers as conditionals on the pad state, which clears the bits of held buttons
+    #[test]
+    fn ir_joker() {
+        let instructions = vec!(
+            Instruction::Joker { addr: 0x003B4FE2, buttons: 0x0009, skip_lines: Skip::Lines(1) },
+            Instruction::Write32 { addr: 0x004865E0, value: 0x00114288 },
+        );
+
+        assert_eq!(ir::encode(CodeDevice::STD, &instructions).unwrap(), vec!(
+            0xD03B4FE2, 0x0000FFF6,
+            0x204865E0, 0x00114288,
+        ));
+        assert_eq!(ir::encode(CodeDevice::ARMAX, &instructions).unwrap(), vec!(
+            0x0A3B4FE2, 0x0000FFF6,
+            0x044865E0, 0x00114288,
+        ));
+    }
+}
+
+
+#[cfg(test)]
+mod disasm_tests {
+    use crate::armax;
+    use crate::cheat::Cheat;
+    use crate::disasm;
+    use crate::formats::CodeDevice;
+    use crate::omniconvert;
+
+    // Disassemble a decrypted ActionReplay MAX cheat, verifier included
+    #[test]
+    fn disasm_armax() {
+        let mut state = omniconvert::State::new();
+        let tokens = omniconvert::read_input("Have All Trinities\nPMGE-KJ9D-X4WRN\nQJNC-EWMH-UQ48H", state.incrypt.code.format);
+        let cheat = omniconvert::build_cheat_list(tokens, &mut vec!()).unwrap().remove(0);
+        let cheat = armax::decrypt::decrypt_cheat(cheat, &state.armax_seeds, &mut state.ar2_input).unwrap();
+
+        let lines = disasm::disassemble(&cheat, CodeDevice::ARMAX).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("verifier: game 0x029E, cheat 0x06BC5, USA"), "{}", lines[0]);
+        assert_eq!(lines[1], "003F38AB 0000007F  8-bit write 0x7F → 0x003F38AB");
+    }
+
+    // Disassemble standard codes, counting the lines conditionals skip and spotting jokers
+    #[test]
+    fn disasm_standard() {
+        let mut cheat = Cheat::new();
+        cheat.codes = vec!(
+            0xE0030000, 0x003B4FE2,     // 16-bit equal, for the next 3 lines
+            0x30400000, 0x004A0010,     // 32-bit increment, taking up 2 lines
+            0x00000005, 0x00000000,
+            0x204865E0, 0x00114288,
+            0xD03B4FE2, 0x0000F3FF,     // Joker
+            0x104A0000, 0x0000FFFF,
+        );
+
+        assert_eq!(disasm::disassemble(&cheat, CodeDevice::STD).unwrap(), vec!(
+            "E0030000 003B4FE2  if 16-bit at 0x003B4FE2 == 0x0000, run the next 3 lines",
+            "30400000 004A0010  32-bit increment 0x004A0010 by 0x00000005",
+            "00000005 00000000  (continued)",
+            "204865E0 00114288  32-bit write 0x00114288 → 0x004865E0",
+            "D03B4FE2 0000F3FF  if 16-bit at 0x003B4FE2 == 0xF3FF, run the next line (joker for L1 + R1?)",
+            "104A0000 0000FFFF  16-bit write 0xFFFF → 0x004A0000",
+        ));
+    }
+}
+
+
+#[cfg(test)]
+mod asm_tests {
+    use crate::asm;
+    use crate::error::ErrorKind;
+    use crate::formats::{CodeDevice, FORMATS};
+    use crate::omniconvert::ConvertOptions;
+    use crate::token::Span;
+
+    // Assemble instructions into standard codes
+    #[test]
+    fn asm_standard() {
+        let cheats = asm::assemble(
+"Infinite Health
+# Hold L1 + R1 to refill
+joker 0x003B4FE2 L1+R1 then 1
+write16 0x004A0000 = 0x03E7
+
+Max Money
+if32 0x004A0010 == 0 then all
+inc32 0x004A0010 += 0x1000
+dec8 0x004A0014 -= 1", CodeDevice::STD).unwrap();
+
+        assert_eq!(cheats.len(), 2);
+        assert_eq!(cheats[0].name, "Infinite Health");
+        assert_eq!(cheats[0].comment, "Hold L1 + R1 to refill");
+        assert_eq!(cheats[0].codes, vec!(
+            0xD03B4FE2, 0x0000F3FF,
+            0x104A0000, 0x000003E7,
+        ));
+        assert_eq!(cheats[1].codes, vec!(
+            0xC04A0010, 0x00000000,
+            0x30400000, 0x004A0010,
+            0x00001000, 0x00000000,
+            0x30100001, 0x004A0014,
+        ));
+        assert_eq!(cheats[1].spans[2], Span { line: 8, column: 1, length: 26 });
+    }
+
+    // Count conditional lines on the device, not instructions
+    #[test]
+    fn asm_conditional_lines() {
+        let cheats = asm::assemble("if16 0x004A0000 == 0xFFFF then 2\ninc32 0x004A0010 += 5\nwrite8 0x003F38AB = 0x7F", CodeDevice::STD).unwrap();
+        assert_eq!(cheats[0].name, "New Cheat");
+        assert_eq!(&cheats[0].codes[0..2], &[0xE003FFFF, 0x004A0000]);
+    }
+
+    // Point syntax errors at the offending text
+    #[test]
+    fn asm_errors() {
+        let error = asm::assemble("Max Money\nwrite32 0x004A0010 0x7F", CodeDevice::STD).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Syntax { expected: "=" });
+        assert_eq!(error.span, Some(Span { line: 2, column: 20, length: 4 }));
+
+        let error = asm::assemble("if16 0x004A0000 == 0xFFFF", CodeDevice::STD).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Syntax { expected: "then" });
+        assert_eq!(error.span, Some(Span { line: 1, column: 26, length: 1 }));
+
+        let error = asm::assemble("write8 0x003F38AB = 0x100", CodeDevice::STD).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Syntax { expected: "value that fits the instruction's width" });
+
+        // Operations the device doesn't have
+        let error = asm::assemble("Copy\ncopy 0x0049C000 to 0x0049D000 count 16", CodeDevice::ARMAX).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Untranslatable("byte copy"));
+        assert_eq!(error.cheat, Some("Copy".to_string()));
+        assert_eq!(error.span.map(|span| span.line), Some(2));
+
+        // Mistyped mnemonics don't start a new cheat
+        let error = asm::assemble("Max Money\nwirte32 0x003F38AC = 0x1", CodeDevice::STD).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Syntax { expected: "instruction mnemonic" });
+        assert_eq!(error.span, Some(Span { line: 2, column: 1, length: 7 }));
+    }
+
+    // Read comments with any of the parser's prefixes
+    #[test]
+    fn asm_comments() {
+        let cheats = asm::assemble("Max Money\n# Visit a shop\n// first\n; then save\nwrite32 0x004A0010 = 0x7F", CodeDevice::STD).unwrap();
+        assert_eq!(cheats.len(), 1);
+        assert_eq!(cheats[0].comment, "Visit a shop\nfirst\nthen save");
+        assert_eq!(cheats[0].codes, vec!(0x204A0010, 0x0000007F));
+    }
+
+    // Compile to encrypted ActionReplay MAX codes, then read them back
+    #[test]
+    fn asm_compile_armax() {
+        let error = crate::compile("Save Anywhere\nwrite32 0x004865E0 = 0x00114288", &FORMATS[8], &ConvertOptions::default()).err().unwrap();
+        assert!(matches!(error.kind, ErrorKind::InvalidOption { option: "game ID", .. }));
+
+        let options = ConvertOptions { game_id: Some(0x029E), ..Default::default() };
+        let output = crate::compile("Save Anywhere\nwrite32 0x004865E0 = 0x00114288", &FORMATS[8], &options).unwrap();
+        assert_eq!(output.game.cheats[0].codes.len(), 4);
+
+        let output = crate::convert(&output.text, &FORMATS[8], &FORMATS[0], &ConvertOptions::default()).unwrap();
+        assert_eq!(output.game.id, 0x029E);
+        assert_eq!(output.text, "Save Anywhere\n204865E0 00114288");
+    }
+}
+
+#[cfg(test)]
+mod detect_tests {
+    use crate::detect_format;
+    use crate::error::{Error, ErrorKind};
+    use crate::formats::{CodeFormat, FORMATS};
+    use crate::ConvertOptions;
+
+    // Raw "Have All Trinities" and "Save Anywhere" codes for Kingdom Hearts (USA), plus a 16-bit constant write
+    const TEST_CHEATS_RAW: &str = "Have All Trinities\n003F38AB 0000007F\n\nSave Anywhere\n204865E0 00114288\n104A0000 0000FFFF";
+
+    // Detect every code type from cheats converted to it, among the best guesses. Unsupported ciphers are left out.
+    #[test]
+    fn detect_converted() {
+        let options = ConvertOptions { game_id: Some(0x029E), ..Default::default() };
+        for code in FORMATS.iter() {
+            let output = match crate::convert(TEST_CHEATS_RAW, &FORMATS[0], code, &options) {
+                Err(Error { kind: ErrorKind::UnverifiedCipher(_), .. }) => continue,
+                output => output.unwrap(),
+            };
+            let guesses = detect_format(&output.text);
+
+            let best = guesses[0].score;
+            assert!(guesses.iter().take_while(|guess| guess.score == best).any(|guess| guess.code == *code), "{}", code.name);
+        }
+    }
+
+    // Signals that pick out one code type over others sharing a device
+    #[test]
+    fn detect_signals() {
+        let guesses = detect_format("Have All Trinities\nPMGE-KJ9D-X4WRN\nQJNC-EWMH-UQ48H");
+        assert_eq!(guesses.len(), 1);
+        assert!(guesses[0].code == FORMATS[8]);
+        assert!(guesses[0].reasons.contains(&"CRC matches for 1 of 1 cheats".to_string()));
+
+        // Typos still look like ARMAX codes
+        let guesses = detect_format("NMGE-KJ9D-X4WRM\nQJNC-EWMH-UQ48H");
+        assert_eq!(guesses.len(), 1);
+        assert_eq!(guesses[0].score, 10);
+
+        // Common key header
+        let guesses = detect_format("Have All Trinities\nBEEFC0DE 00000000\n2AF09C1B 9D3E5C07");
+        let cb7 = guesses.iter().find(|guess| guess.code.format == CodeFormat::CB7).unwrap();
+        assert!(cb7.reasons.contains(&"has a BEEFC0DE header for the common V7 key".to_string()));
+
+        // Encrypted key lines
+        let raw = TEST_CHEATS_RAW.replace("Trinities\n", "Trinities\nDEADFACE 00000102\n");
+        let output = crate::convert(&raw, &FORMATS[2], &FORMATS[7], &ConvertOptions::default()).unwrap();
+        let guesses = detect_format(&output.text);
+        assert!(guesses[0].code.format == CodeFormat::AR2);
+        assert!(guesses[0].reasons.contains(&"decrypts DEADFACE key lines".to_string()));
+
+        // Nothing to read
+        assert!(detect_format("Infinite Health").is_empty());
+    }
+}
@@ -1,8 +1,12 @@
-use crate::formats::{CodeFormat, CodeType, FORMATS};
-use crate::game::{Region};
+use crate::formats::{CodeDevice, CodeFormat, CodeType, FORMATS};
+use crate::game::{Game, Region};
 use crate::cheat::{Cheat, CheatStates};
-use crate::token::{Token, TokenType};
+use crate::error::{Error, ErrorKind, Result, Warning, WarningKind};
+use crate::token::{hex_digits, is_code_word, is_raw_code, split_code, split_line, Span, Token, TokenType, COMMENT_PREFIXES};
+use crate::translate;
 use crate::armax;
+use crate::asm;
+use crate::ar1;
 use crate::ar2;
 
 // Which mode is represented by a given operation/options
@@ -12,10 +16,11 @@ pub enum CryptMode {
 }
 
 // Parsing method
-enum ParserType {
-    Simple,
-    Strict,
-    Reformat,
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ParserType {
+    Simple,     // Skip over anything that isn't a cheat, warning about it
+    Strict,     // Reject anything that isn't a cheat
+    Reformat,   // TODO: Reformat input without converting it
 }
 
 // Encryption/decryption options
@@ -36,16 +41,33 @@ pub struct State {
     pub armax_seeds: [u32; 32],
     // AR2 seeds
     pub ar2_seeds: [u8; 4],
+    // AR2 seeds for input and output, following DEADFACE key changes
+    pub ar2_input: [u8; 4],
+    pub ar2_output: [u8; 4],
+    // AR2 seed key for output, and whether the next cheat still has to switch to it
+    ar2_key:    Option<u32>,
+    ar2_rekey:  bool,
     // Game region
     region:     Region,
+    // ARMAX disc hash for enable codes
+    // TODO: Compute from the game's ELF once armMakeDiscHash() has been checked against a known disc hash
+    disc_hash:  Option<u32>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl State {
     // Initialize the default environment
     pub fn new() -> Self {
+        let ar2_seeds = ar2::seeds::generate();
+
         // Return default State object
         State {
-            // Default to ARMAX input
+            // Default to ARMAX input. detect::detect_format() can guess it from the input instead.
             incrypt: CryptOpt {
                 mode: CryptMode::Input,
                 code: FORMATS[8].clone(),
@@ -58,10 +80,424 @@ impl State {
             parser: ParserType::Simple,
             armax_verifier: armax::VerifierMode::Auto,
             armax_seeds: armax::seeds::generate(),
-            ar2_seeds: ar2::seeds::generate(),
+            ar2_seeds,
+            ar2_input: ar2_seeds,
+            ar2_output: ar2_seeds,
+            ar2_key: None,
+            ar2_rekey: false,
+            region: Region::Unknown,
+            disc_hash: None,
+        }
+    }
+
+    // Choose the input code type
+    pub fn set_input(&mut self, code: &CodeType) -> Result<()> {
+        if !FORMATS.contains(code) {
+            return Err(invalid_option("input code type", "must be one of FORMATS"));
+        }
+        check_cipher(code.format)?;
+        self.incrypt.code = code.clone();
+        Ok(())
+    }
+
+    // Choose the output code type
+    pub fn set_output(&mut self, code: &CodeType) -> Result<()> {
+        if !FORMATS.contains(code) {
+            return Err(invalid_option("output code type", "must be one of FORMATS"));
+        }
+        check_cipher(code.format)?;
+        if code.device != CodeDevice::ARMAX && self.armax_verifier == armax::VerifierMode::Manual {
+            return Err(invalid_option("output code type", "manual verifier mode needs ARMAX output"));
+        }
+        if code.format != CodeFormat::AR2 && self.ar2_key.is_some() {
+            return Err(invalid_option("output code type", "AR2 keys need AR2 output"));
+        }
+        self.outcrypt.code = code.clone();
+        Ok(())
+    }
+
+    // Choose the parsing method
+    pub fn set_parser(&mut self, parser: ParserType) -> Result<()> {
+        if parser == ParserType::Reformat {
+            return Err(invalid_option("parser", "reformatting isn't supported yet"));
+        }
+        self.parser = parser;
+        Ok(())
+    }
+
+    // Choose how ARMAX verifiers are built
+    pub fn set_verifier_mode(&mut self, mode: armax::VerifierMode) -> Result<()> {
+        if mode == armax::VerifierMode::Manual {
+            if self.outcrypt.code.device != CodeDevice::ARMAX {
+                return Err(invalid_option("verifier mode", "manual verifier mode needs ARMAX output"));
+            }
+            if self.disc_hash.is_some() {
+                return Err(invalid_option("verifier mode", "disc hashes are only added in auto verifier mode"));
+            }
+        }
+        self.armax_verifier = mode;
+        Ok(())
+    }
+
+    // Force the game region, instead of taking it from the input. Unknown takes it from the input.
+    pub fn set_region(&mut self, region: Region) {
+        self.region = region;
+    }
+
+    // Choose the disc hash added to ARMAX enable code verifiers
+    pub fn set_disc_hash(&mut self, disc_hash: Option<u32>) -> Result<()> {
+        if disc_hash.is_some() && self.armax_verifier == armax::VerifierMode::Manual {
+            return Err(invalid_option("disc hash", "disc hashes are only added in auto verifier mode"));
+        }
+        self.disc_hash = disc_hash;
+        Ok(())
+    }
+
+    // Choose the AR2 seed key for output, switched to by a DEADFACE line at the start of the first cheat
+    pub fn set_ar2_key(&mut self, key: Option<u32>) -> Result<()> {
+        if let Some(key) = key {
+            if self.outcrypt.code.format != CodeFormat::AR2 {
+                return Err(invalid_option("AR2 key", "AR2 keys need AR2 output"));
+            }
+            let seeds = ar2::seeds::regenerate(key);
+            if seeds[0] > 7 || seeds[2] > 7 || ar2::check_seed(seeds[1]).is_err() || ar2::check_seed(seeds[3]).is_err() {
+                return Err(invalid_option("AR2 key", "its seeds are out of range"));
+            }
+        }
+        self.ar2_key = key;
+        self.ar2_rekey = key.is_some();
+        Ok(())
+    }
+
+    pub fn input(&self) -> &CodeType {
+        &self.incrypt.code
+    }
+
+    pub fn output(&self) -> &CodeType {
+        &self.outcrypt.code
+    }
+
+    pub fn parser(&self) -> ParserType {
+        self.parser
+    }
+
+    pub fn verifier_mode(&self) -> armax::VerifierMode {
+        self.armax_verifier
+    }
+
+    pub fn region(&self) -> Region {
+        self.region
+    }
+
+    pub fn disc_hash(&self) -> Option<u32> {
+        self.disc_hash
+    }
+
+    pub fn ar2_key(&self) -> Option<u32> {
+        self.ar2_key
+    }
+
+    // Reset AR2 seeds for input and output, e.g. between games
+    pub fn reset_devices(&mut self) {
+        self.ar2_input = self.ar2_seeds;
+        self.ar2_output = self.ar2_seeds;
+        self.ar2_rekey = self.ar2_key.is_some();
+    }
+}
+
+// Options for convert()
+#[derive(Clone)]
+pub struct ConvertOptions {
+    pub parser:     ParserType,             // Parsing method
+    pub verifier:   armax::VerifierMode,    // How ARMAX verifiers are built
+    pub region:     Region,                 // Game region to force, or Unknown to take it from the input
+    pub game_id:    Option<u32>,            // Game ID for ARMAX verifiers, needed unless the input is ARMAX
+    pub disc_hash:  Option<u32>,            // Disc hash to add to ARMAX enable code verifiers
+    pub ar2_key:    Option<u32>,            // Seed key to switch AR2 output to, instead of the default seeds
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        ConvertOptions {
+            parser: ParserType::Simple,
+            verifier: armax::VerifierMode::Auto,
             region: Region::Unknown,
+            game_id: None,
+            disc_hash: None,
+            ar2_key: None,
+        }
+    }
+}
+
+// Result of convert()
+pub struct ConversionOutput {
+    pub game:       Game,           // Converted game, with every cheat in the output format
+    pub text:       String,         // Converted cheats as text
+    pub warnings:   Vec<Warning>,   // Problems found in the input that didn't stop the conversion
+}
+
+// Convert a block of cheats from one code type to another
+pub fn convert(text: &str, input: &CodeType, output: &CodeType, options: &ConvertOptions) -> Result<ConversionOutput> {
+    // Set up the environment, validating options before reading any input
+    let mut state: State = State::new();
+    state.set_input(input)?;
+    state.set_output(output)?;
+    state.set_parser(options.parser)?;
+    state.set_verifier_mode(options.verifier)?;
+    state.set_region(options.region);
+    state.set_disc_hash(options.disc_hash)?;
+    state.set_ar2_key(options.ar2_key)?;
+
+    // ARMAX verifiers need a game ID, which only ARMAX input carries
+    if output.device == CodeDevice::ARMAX && input.device != CodeDevice::ARMAX && options.game_id.is_none() {
+        return Err(invalid_option("game ID", "ARMAX output needs a game ID unless the input is ARMAX"));
+    }
+
+    // Parse input into cheats
+    log::debug!("Reading input");
+    let tokens = read_input(text, input.format);
+    let mut warnings: Vec<Warning> = vec!();
+    let cheats = build_cheat_list(tokens, &mut warnings)?;
+
+    // Strict parsing doesn't skip over anything
+    if let (ParserType::Strict, Some(warning)) = (state.parser, warnings.first()) {
+        return Err(Error::new(ErrorKind::Skipped(warning.kind.clone())).with_span(warning.span));
+    }
+
+    // Decrypt cheats
+    log::debug!("Decrypting {} cheats", cheats.len());
+    let mut decrypted: Vec<Cheat> = vec!();
+    for cheat in cheats {
+        let mut cheat = decrypt_cheat(&mut state, input.format, cheat)?;
+        cheat.state = CheatStates::Decrypted;
+
+        // A mistyped ARMAX line can still pass its parity check, but not the CRC of the whole cheat
+        if cheat.crc_valid == Some(false) {
+            let span = cheat.spans.first().copied().unwrap_or_default();
+            if state.parser == ParserType::Strict {
+                return Err(Error::new(ErrorKind::Crc).in_cheat(&cheat).with_span(span));
+            }
+            log::warn!("CRC mismatch in cheat {:?}", cheat.name);
+            warnings.push(Warning {
+                kind: WarningKind::CrcMismatch { name: cheat.name.clone() },
+                span,
+            });
         }
+        decrypted.push(cheat);
+    }
+
+    // Pull game metadata from the first ARMAX cheat, unless given
+    let mut game: Game = Game::new();
+    if let Some(first) = decrypted.first().filter(|_| input.device == CodeDevice::ARMAX) {
+        game.id = first.game_id;
+        game.region = match first.region {
+            0 => Region::USA,
+            1 => Region::PAL,
+            2 => Region::Japan,
+            _ => Region::Unknown,
+        };
+    }
+    if let Some(id) = options.game_id {
+        game.id = id;
+    }
+    if state.region != Region::Unknown {
+        game.region = state.region;
+    }
+
+    // Translate cheats to the output device, then encrypt them
+    log::debug!("Translating and encrypting {} cheats", decrypted.len());
+    for cheat in decrypted {
+        let cheat = if input.device == output.device {
+            translate::translate_cheat(&cheat, input.device, output.device)?
+        }
+        else {
+            // ARMAX verifiers aren't codes, so only translate what's after them
+            let cheat = match input.device {
+                CodeDevice::ARMAX => armax::verifier::remove_verifier(&cheat)?,
+                _ => cheat,
+            };
+            let cheat = match translate::translate_cheat(&cheat, input.device, output.device) {
+                Ok(cheat) => cheat,
+                // Enable codes hook into the game differently on every device, so they often have no equivalent
+                Err(e) if cheat.enable_code && matches!(e.kind, ErrorKind::Untranslatable(_)) => {
+                    log::warn!("Dropping enable code {:?}: {}", cheat.name, e);
+                    warnings.push(Warning {
+                        kind: WarningKind::DroppedEnableCode { name: cheat.name.clone() },
+                        span: e.locate(&cheat.spans).span.or(cheat.spans.first().copied()).unwrap_or_default(),
+                    });
+                    continue;
+                },
+                Err(e) => return Err(e.locate(&cheat.spans)),
+            };
+            add_verifier(&state, &game, cheat)?
+        };
+        game.cheats.push(encrypt_cheat(&mut state, output.format, cheat)?);
+    }
+
+    let text = write_output(&game, output);
+
+    Ok(ConversionOutput {
+        game,
+        text,
+        warnings,
+    })
+}
+
+// Assemble cheats written as instructions (see asm::assemble()), then encrypt them to a code type
+pub fn compile(text: &str, output: &CodeType, options: &ConvertOptions) -> Result<ConversionOutput> {
+    // Set up the environment, validating options before reading any input
+    let mut state: State = State::new();
+    state.set_output(output)?;
+    state.set_parser(options.parser)?;
+    state.set_verifier_mode(options.verifier)?;
+    state.set_region(options.region);
+    state.set_disc_hash(options.disc_hash)?;
+    state.set_ar2_key(options.ar2_key)?;
+
+    // ARMAX verifiers need a game ID
+    if output.device == CodeDevice::ARMAX && options.game_id.is_none() {
+        return Err(invalid_option("game ID", "ARMAX output needs a game ID"));
     }
+    let mut game: Game = Game::new();
+    game.id = options.game_id.unwrap_or(0);
+    game.region = state.region;
+
+    log::debug!("Assembling input");
+    for cheat in asm::assemble(text, output.device)? {
+        let cheat = add_verifier(&state, &game, cheat)?;
+        game.cheats.push(encrypt_cheat(&mut state, output.format, cheat)?);
+    }
+
+    let text = write_output(&game, output);
+
+    Ok(ConversionOutput {
+        game,
+        text,
+        warnings: vec!(),
+    })
+}
+
+// Add a verifier to a cheat translated to ARMAX
+fn add_verifier(state: &State, game: &Game, mut cheat: Cheat) -> Result<Cheat> {
+    if state.outcrypt.code.device != CodeDevice::ARMAX {
+        return Ok(cheat);
+    }
+
+    // Only ARMAX cheats carry a code ID, so number the others in the order they're added to the game
+    cheat.id = game.cheats.len() as u32 + 1;
+
+    let expansions = armax::verifier::expansions_for(&cheat, state.disc_hash);
+    armax::verifier::add_verifier(&cheat, game, &expansions)
+}
+
+// Error for a conversion option that can't be used
+fn invalid_option(option: &'static str, reason: &'static str) -> Error {
+    Error::new(ErrorKind::InvalidOption { option, reason })
+}
+
+// Write the cheats of a game as text in the style of a code type, separated by blank lines as read_input() expects
+pub fn write_output(game: &Game, code: &CodeType) -> String {
+    game.cheats.iter()
+        .map(|cheat| write_cheat(cheat, code))
+        .collect::<Vec<String>>()
+        .join("\n\n")
+}
+
+// Write a cheat's name, comment lines, then codes in the style of a code type
+pub fn write_cheat(cheat: &Cheat, code: &CodeType) -> String {
+    let mut lines: Vec<String> = vec!(cheat.name.clone());
+
+    // Keep the prefix, so comments read back as comments rather than names or codes
+    if !cheat.comment.is_empty() {
+        lines.extend(cheat.comment.split('\n').map(|line| match line {
+            "" => "#".to_string(),
+            _ => format!("# {}", line),
+        }));
+    }
+
+    match code.format {
+        CodeFormat::ARMAX => {
+            // XXXX-XXXX-XXXXX
+            let pairs: Vec<(u32, u32)> = cheat.codes.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect();
+            lines.extend(armax::encrypt::octets_to_alpha(&pairs));
+        },
+        CodeFormat::AR1 | CodeFormat::AR2 | CodeFormat::CB | CodeFormat::CB7 |
+        CodeFormat::GS3 | CodeFormat::GS5 | CodeFormat::MAXRAW | CodeFormat::RAW => {
+            // XXXXXXXX YYYYYYYY
+            lines.extend(cheat.codes.chunks_exact(2).map(|pair| format!("{:08X} {:08X}", pair[0], pair[1])));
+        },
+    }
+
+    lines.join("\n")
+}
+
+// Refuse formats whose cipher isn't implemented yet, rather than make up codes
+// TODO: Port the CodeBreaker V1-V7 ciphers from cb2_crypto.c, with known-answer tests from published codes.
+// TODO: Port the GS3 cipher from gs3.c with known-answer tests, and check the GS5 verifier against published GS5 codes.
+fn check_cipher(format: CodeFormat) -> Result<()> {
+    let cipher = match format {
+        CodeFormat::CB => "CodeBreaker V1-V6",
+        CodeFormat::CB7 => "CodeBreaker V7",
+        CodeFormat::GS3 | CodeFormat::GS5 => "GameShark V3+",
+        _ => return Ok(()),
+    };
+    Err(Error::new(ErrorKind::UnverifiedCipher(cipher)))
+}
+
+// Decrypt a cheat from a given input format to its device's raw codes
+pub fn decrypt_cheat(state: &mut State, format: CodeFormat, cheat: Cheat) -> Result<Cheat> {
+    check_cipher(format).map_err(|e| e.in_cheat(&cheat))?;
+
+    // Keep where each line came from to point errors at it
+    let spans = cheat.spans.clone();
+
+    let output = match format {
+        CodeFormat::AR1 => ar1::decrypt::decrypt_cheat(cheat),
+        CodeFormat::AR2 => {
+            let mut output = cheat;
+            output.codes = ar2::decrypt::decrypt_cheat(output.codes.clone(), &mut state.ar2_input)
+                .map_err(|e| e.in_cheat(&output))?;
+            Ok(output)
+        }
+        CodeFormat::ARMAX => armax::decrypt::decrypt_cheat(cheat, &state.armax_seeds, &mut state.ar2_input),
+        CodeFormat::CB | CodeFormat::CB7 | CodeFormat::GS3 | CodeFormat::GS5 => unreachable!("refused by check_cipher()"),
+        CodeFormat::MAXRAW | CodeFormat::RAW => Ok(cheat),
+    };
+
+    output.map_err(|e| e.locate(&spans))
+}
+
+// Encrypt a cheat of raw codes to a given output format
+// Remarks: ARMAX output expects the cheat to already include its verifier
+pub fn encrypt_cheat(state: &mut State, format: CodeFormat, cheat: Cheat) -> Result<Cheat> {
+    check_cipher(format).map_err(|e| e.in_cheat(&cheat))?;
+
+    // Keep where each line came from to point errors at it
+    let spans = cheat.spans.clone();
+
+    let output = match format {
+        CodeFormat::AR1 => ar1::encrypt::encrypt_cheat(cheat),
+        CodeFormat::AR2 => {
+            let mut output = cheat;
+            output.codes = match state.ar2_key.filter(|_| state.ar2_rekey) {
+                // Count lines from the start of the input, without the added key line
+                Some(key) => ar2::encrypt::encrypt_cheat_rekeyed(output.codes.clone(), &mut state.ar2_output, key)
+                    .map_err(|mut e| {
+                        e.line = e.line.map(|line| line.saturating_sub(1));
+                        e.in_cheat(&output)
+                    })?,
+                None => ar2::encrypt::encrypt_cheat(output.codes.clone(), &mut state.ar2_output)
+                    .map_err(|e| e.in_cheat(&output))?,
+            };
+            state.ar2_rekey = false;
+            Ok(output)
+        }
+        CodeFormat::ARMAX => armax::encrypt::encrypt_cheat(cheat, &state.armax_seeds, &mut state.ar2_output),
+        CodeFormat::CB | CodeFormat::CB7 | CodeFormat::GS3 | CodeFormat::GS5 => unreachable!("refused by check_cipher()"),
+        CodeFormat::MAXRAW | CodeFormat::RAW => Ok(cheat),
+    };
+
+    output.map_err(|e| e.locate(&spans))
 }
 
 // Tokenize input based on a given format
@@ -71,50 +507,84 @@ pub fn read_input(input: &str, format: CodeFormat) -> Vec<Token> {
     // Output tokens
     let mut output: Vec<Token> = vec![];
 
+    // Whether no name or code has been read since the last blank line, so a line is where a cheat's name goes
+    let mut block_start = true;
+
     // Iterate each line
-    println!("[-] Iterating input lines...");
-    for line in input.lines() {
+    log::debug!("Iterating input lines...");
+    for (number, line) in input.lines().enumerate() {
 
-        println!("[?] Line: \"{}\"", line);
+        log::trace!("Line {}: \"{}\"", number + 1, line);
 
         if !line.is_empty() {
             // Line has some tokens in it.
 
             // Flag to indicate if we read a code address last iteration and are expecting a value
             let mut expecting_value = false;
+            // Flag to indicate if we just read a whole code, which may be followed by a comment
+            let mut after_code = false;
             // Iterate tokens on this line
-            println!("[-] Iterating tokens...");
-            for (i, t) in line.split_whitespace().enumerate() {
-                // TODO: Fix comment parsing (i.e. actually _do_ it)
-                // Ignore comment lines starting with '#'
-                if i == 0 && t.chars().nth(0) == Some('#') {
-                    break;
+            log::trace!("Iterating tokens...");
+            let pieces = split_line(line, number + 1);
+            for (i, &(t, span)) in pieces.iter().enumerate() {
+                // Comments take up the rest of the line, either on their own or after a code
+                if !expecting_value && (i == 0 || after_code) {
+                    if let Some(prefix) = COMMENT_PREFIXES.iter().find(|prefix| t.starts_with(*prefix)) {
+                        let rest: String = line.chars().skip(span.column - 1).collect();
+                        let rest = rest.trim_end();
+                        output.push(Token {
+                            string:     String::from(rest[prefix.len()..].trim()),
+                            is_multi:   false,
+                            types:      vec!(TokenType::Comment),
+                            span:       Span { length: rest.chars().count(), ..span },
+                        });
+                        break;
+                    }
                 }
 
+                // Codes start a line or follow another code, unless the rest of the line is hex words too
+                let code_position = i == 0 || after_code || pieces[i..].iter().all(|(word, _)| hex_digits(word).is_some());
+
                 // Identify token type
-                let mut token_type: TokenType;
-                if !expecting_value {
-                    // Identify as normal, handling ARMAX codes as necessary
-                    token_type = Token::identify_type(t, format == CodeFormat::ARMAX);
-                    // If we just read a code, set it to be an address & set the next iteration to expect a code value
-                    if token_type == TokenType::Code {
-                        token_type = TokenType::CodeAddress;
-                        expecting_value = true;
-                    }
-                }
-                else {
-                    // TODO: Avoid blindly expecting code values
-                    // We're expecting a code address, since we read a code value last iteration
+                let token_type: TokenType;
+                if expecting_value {
+                    // We're expecting a code value, since we read a code address last iteration
                     token_type = TokenType::CodeValue;
                     // Reset the 'expecting' flag
                     expecting_value = false;
                 }
+                else if format != CodeFormat::ARMAX && code_position && is_raw_code(t) {
+                    // Address and value in a single token
+                    token_type = TokenType::Code;
+                }
+                else if format != CodeFormat::ARMAX && code_position && is_code_word(t)
+                    && pieces.get(i + 1).is_some_and(|(next, _)| is_code_word(next))
+                    && [t, pieces[i + 1].0].iter().any(|word| hex_digits(word).is_some_and(|d| d.len() == 8)) {
+                    // Address followed by a value. Words with the wrong number of digits are still read, as long as
+                    // the other one is right, so they can be rejected where they are instead of being read as text.
+                    token_type = TokenType::CodeAddress;
+                    expecting_value = true;
+                }
+                else if format != CodeFormat::ARMAX && !block_start && pieces.len() == 1 && hex_digits(t).is_some_and(|d| d.len() == 8) {
+                    // Address alone on its line, missing its value
+                    token_type = TokenType::CodeAddress;
+                }
+                else {
+                    // Identify as normal, handling ARMAX codes as necessary. A hex word alone where a name goes is the name.
+                    token_type = match Token::identify_type(t, format == CodeFormat::ARMAX) {
+                        TokenType::HexOctet if block_start && pieces.len() == 1 => TokenType::String,
+                        token_type => token_type,
+                    };
+                }
+
+                after_code = matches!(token_type, TokenType::Code | TokenType::CodeValue | TokenType::ARMAXCode);
 
                 // Add Token object to output
                 output.push(Token {
                     string:     String::from(t),
                     is_multi:   false,
                     types:      vec!(token_type),
+                    span,
                 });
             }
             // Set the last token to also be an end-of-line token
@@ -122,9 +592,15 @@ pub fn read_input(input: &str, format: CodeFormat) -> Vec<Token> {
                 last.is_multi = true;
                 last.types.push(TokenType::EndOfLine);
             }
+
+            // Anything but a comment line takes the place of the name
+            if pieces.first().is_some_and(|(t, _)| !COMMENT_PREFIXES.iter().any(|prefix| t.starts_with(prefix))) {
+                block_start = false;
+            }
         }
         else {
             // Line is empty.
+            block_start = true;
 
             // Set the previous token to also be an end-of-block token
             if let Some(last) = output.last_mut() {
@@ -137,20 +613,21 @@ pub fn read_input(input: &str, format: CodeFormat) -> Vec<Token> {
                 string:     String::from("\n"),
                 is_multi:   true,
                 types:      vec!(TokenType::String, TokenType::NewLine, TokenType::EndOfLine),
+                span:       Span { line: number + 1, column: 1, length: 0 },
             })
         }
 
-        // TODO: Go back and set pairs of hex octets to be code address/code values
     }
 
-    println!("[-] Done iterating input lines.");
+    log::debug!("Done iterating input lines.");
 
     // Clean up input and delineate individual cheats
-    println!("[-] Cleaning up input");
+    log::debug!("Cleaning up input");
     for t in output.iter_mut() {
-        // Consider all remaining raw hex octets to actually be strings
+        // Consider all remaining raw hex octets to actually be strings, remembering they looked like codes
         if t.types.first() == Some(&TokenType::HexOctet) {
-            t.types[0] = TokenType::String;
+            t.is_multi = true;
+            t.types.insert(0, TokenType::String);
         }
         // TODO: Should we identify 'EndCode' tokens the same way as the original code?
         // It's currently done as part of reading an empty line.
@@ -169,7 +646,8 @@ pub fn read_input(input: &str, format: CodeFormat) -> Vec<Token> {
 }
 
 // Build a vec of Cheat objects from a vec of Token objects
-pub fn build_cheat_list(token_list: Vec<Token>) -> Vec<Cheat> {
+// Remarks: Tokens that are skipped over are added to the given warnings
+pub fn build_cheat_list(token_list: Vec<Token>, warnings: &mut Vec<Warning>) -> Result<Vec<Cheat>> {
     // Output cheat list
     let mut output: Vec<Cheat> = vec![];
 
@@ -182,6 +660,12 @@ pub fn build_cheat_list(token_list: Vec<Token>) -> Vec<Cheat> {
     // String currently being built
     let mut s = String::new();
 
+    // Where the text of the cheat currently being built starts
+    let mut text_span: Option<Span> = None;
+
+    // Comment lines of the cheat currently being built
+    let mut comments: Vec<String> = vec!();
+
     // Iterate through tokens to build a list of cheats
     let mut tokens = token_list.iter();
     loop {
@@ -197,12 +681,22 @@ pub fn build_cheat_list(token_list: Vec<Token>) -> Vec<Cheat> {
         if token.types.contains(&TokenType::String) {
             // Handle strings - name or comments
 
+            // Hex octets only end up here if they couldn't be read as part of a code
+            if token.types.contains(&TokenType::HexOctet) {
+                log::warn!("Reading hex octet as text: {:?}", token);
+                warnings.push(Warning {
+                    kind: WarningKind::HexAsText { token: token.string.clone() },
+                    span: token.span,
+                });
+            }
+
             // TODO: Clean up cheat name/comment reading process (ported pretty directly from C source)
 
             if !token.types.contains(&TokenType::NewLine) {
                 // Token isn't a newline
 
                 s += &token.string;
+                text_span.get_or_insert(token.span);
 
                 if token.types.contains(&TokenType::EndOfLine) {
 
@@ -225,116 +719,161 @@ pub fn build_cheat_list(token_list: Vec<Token>) -> Vec<Cheat> {
 
                 // Found a newline after reading the name, indicating the end of one cheat and start of another.
                 // Add current cheat to output list, then start a new one.
-
-                // Set built string to cheat's comment
-                cheat.comment = String::from(s);
-                s = String::new();
-
-                // Set cheat as parsed
-                cheat.state = CheatStates::Parsed;
-
-                output.push(cheat.clone());
-
-                cheat = Cheat::new();
+                take_comment(&mut s, &mut comments, reading_name);
+                push_cheat(&mut output, &mut cheat, &mut comments);
+                text_span = None;
 
                 reading_name = true;
             }
         }
-        else if token.types.contains(&TokenType::CodeAddress) {
-            // Handle code address token
-
-            // If current token is address, next token must be value.
-            // Take next token as value for current address token.
-            if let Some(next_token) = tokens.next() {
-                // Attempt to parse the address/value octets
-                if let (Ok(address), Ok(value)) =
-                (hex::decode(&token.string), hex::decode(&next_token.string))
-                {
-                    // Double-check our length
-                    if address.len() < 4 || value.len() < 4 {
-                        // TODO: Handle parsing errors gracefully
-                        println!("[!] Received address/value of lengths {}/{}", address.len(), value.len());
-                    }
-                    else {
-                        // Add parsed octets, combining u8s to form a u32.
-                        // Address octet
-                        cheat.codes.push(
-                            ((address[0] as u32) << 3) +
-                            ((address[1] as u32) << 2) +
-                            ((address[2] as u32) << 1) +
-                            (address[3] as u32));
-                        // Value octet
-                        cheat.codes.push(
-                            ((value[0] as u32) << 3) +
-                            ((value[1] as u32) << 2) +
-                            ((value[2] as u32) << 1) +
-                            (value[3] as u32));
-                    }
-                }
-                else {
-                    // TODO: Handle parsing errors gracefully
-                    //println!("[!] Unable to parse address/value pair: ({}/{})", &token.string, &value.string);
-                }
-
-                // If we hit the end of a text/token block, start a new cheat.
-                if next_token.types.contains(&TokenType::EndOfBlock) {
-
-                    // Set cheat as parsed
-                    cheat.state = CheatStates::Parsed;
-
-                    // Add to output list
-                    output.push(cheat.clone());
-
-                    // Start a new cheat
-                    cheat = Cheat::new();
-                    reading_name = true;
-                }
+        else if token.types.contains(&TokenType::Comment) {
+            // Handle comments, keeping them in order with any comment text read so far
+            take_comment(&mut s, &mut comments, reading_name);
+            comments.push(token.string.clone());
+
+            // Comments after the last code of a block end the cheat
+            if token.types.contains(&TokenType::EndOfBlock) && !cheat.codes.is_empty() {
+                push_cheat(&mut output, &mut cheat, &mut comments);
+                text_span = None;
+                reading_name = true;
+            }
+        }
+        else if token.types.contains(&TokenType::CodeAddress) || token.types.contains(&TokenType::Code) {
+            // Handle raw codes, either an address token followed by a value token or both in one token
+            take_comment(&mut s, &mut comments, reading_name);
+
+            let line = cheat.codes.len() / 2;
+            let locate = |e: Error| e.in_cheat(&cheat).at_line(line);
+
+            let (address, value, span, last) = if token.types.contains(&TokenType::Code) {
+                // Point at each half of the token, past any separator
+                let (address, value) = split_code(&token.string)
+                    .ok_or_else(|| locate(Error::new(ErrorKind::InvalidHex).with_span(token.span)))?;
+                let value_span = Span {
+                    column: token.span.column + token.string.len() - value.len(),
+                    length: value.len(),
+                    ..token.span
+                };
+                (
+                    parse_code_word(address, Span { length: address.len(), ..token.span }),
+                    parse_code_word(value, value_span),
+                    token.span,
+                    token,
+                )
             }
             else {
-                // TODO: Handle parsing errors gracefully
-                println!("[!] Expected value for final address token: {:?}", token);
+                // If current token is address, next token must be its value on the same line
+                let next_token = tokens.next()
+                    .filter(|_| !token.types.contains(&TokenType::EndOfLine))
+                    .ok_or_else(|| locate(Error::new(ErrorKind::MissingValue).with_span(token.span)))?;
+                (
+                    parse_code_word(&token.string, token.span),
+                    parse_code_word(&next_token.string, next_token.span),
+                    token.span.to(next_token.span),
+                    next_token,
+                )
+            };
+
+            let (address, value) = (address.map_err(locate)?, value.map_err(locate)?);
+            cheat.codes.push(address);
+            cheat.codes.push(value);
+            cheat.spans.push(span);
+
+            // If we hit the end of a text/token block, start a new cheat.
+            if last.types.contains(&TokenType::EndOfBlock) {
+                push_cheat(&mut output, &mut cheat, &mut comments);
+                text_span = None;
+                reading_name = true;
             }
         }
         else if token.types.contains(&TokenType::ARMAXCode) {
             // Handle ARMAX code token
+            take_comment(&mut s, &mut comments, reading_name);
 
             // Remove the dashes
             let raw_chars = token.string.replace("-", "");
 
-            // Attempt to decode the ARMAX string to an address/value pair of octets
-            if let Some(octets) = armax::decrypt::alpha_to_octets(vec!(&raw_chars)) {
-                // Add the octets to our code list
-                for octet in octets {
-                    cheat.codes.push(octet.0);
-                    cheat.codes.push(octet.1);
-                }
-            }
-            else {
-                // TODO: Handle parsing errors gracefully
-                println!("[!] Unable to parse ARMAX code to octets: {:?}", &token.string);
+            // Decode the ARMAX string to an address/value pair of octets
+            let octets = armax::decrypt::alpha_to_octets(vec!(&raw_chars)).map_err(|mut e| {
+                // Point at the bad character itself when we know which one it is, skipping over dashes
+                let span = match e.kind {
+                    ErrorKind::InvalidCharacter { index, .. } => token.string
+                        .chars()
+                        .enumerate()
+                        .filter(|(_, c)| *c != '-')
+                        .nth(index)
+                        .map_or(token.span, |(offset, _)| token.span.at(offset)),
+                    _ => token.span,
+                };
+                e.line = Some(cheat.codes.len() / 2);
+                e.in_cheat(&cheat).with_span(span)
+            })?;
+
+            // Add the octets to our code list
+            for octet in octets {
+                cheat.codes.push(octet.0);
+                cheat.codes.push(octet.1);
+                cheat.spans.push(token.span);
             }
 
             // If we hit the end of a text/token block, start a new cheat.
             if token.types.contains(&TokenType::EndOfBlock) {
-                // Set cheat as parsed
-                cheat.state = CheatStates::Parsed;
-
-                // Add to output list
-                output.push(cheat.clone());
-
-                // Start a new cheat
-                cheat = Cheat::new();
+                push_cheat(&mut output, &mut cheat, &mut comments);
+                text_span = None;
                 reading_name = true;
             }
         }
         else {
             // Unhandled token
-            println!("[!] Unhandled token of type(s) {:?} - {:?}", token.types, token);
+            log::warn!("Skipping token of type(s) {:?} - {:?}", token.types, token);
+            warnings.push(Warning {
+                kind: WarningKind::SkippedToken { token: token.string.clone(), types: token.types.clone() },
+                span: token.span,
+            });
         }
 
     }
 
-    output
+    // Text after the last code doesn't belong to any cheat
+    if let Some(span) = text_span {
+        if cheat.codes.is_empty() {
+            let text = if reading_name { s } else { format!("{} {}", cheat.name, s) };
+            log::warn!("Skipping text after the last code: {:?}", text);
+            warnings.push(Warning {
+                kind: WarningKind::SkippedText { text: text.trim_end().to_string() },
+                span,
+            });
+        }
+    }
+
+    Ok(output)
+}
+
+// Parse an address or value of a raw code: 8 hex digits in either case, optionally prefixed with 0x
+fn parse_code_word(word: &str, span: Span) -> Result<u32> {
+    hex_digits(word)
+        .filter(|digits| digits.len() == 8)
+        .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+        .ok_or_else(|| Error::new(ErrorKind::InvalidHex).with_span(span))
+}
+
+// Text read after a cheat's name is part of its comment
+fn take_comment(s: &mut String, comments: &mut Vec<String>, reading_name: bool) {
+    if !reading_name {
+        comments.extend(s.lines().map(String::from));
+        s.clear();
+    }
+}
+
+// Add a finished cheat to the output with its comments, then start a new one
+fn push_cheat(output: &mut Vec<Cheat>, cheat: &mut Cheat, comments: &mut Vec<String>) {
+    cheat.comment = comments.join("\n");
+    comments.clear();
+
+    // Set cheat as parsed
+    cheat.state = CheatStates::Parsed;
+
+    output.push(std::mem::take(cheat));
 }
 
 /*
@@ -379,8 +918,6 @@ pub fn minimal_conversion() {
     println!("--> Begin conversion...");
     let mut state: State = State::new();
 
-    // TODO: Build GS3 seeds
-
     // Initialize game object
     println!("[-] Initializing game object");
     let mut game: Game = Game::new();
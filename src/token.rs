@@ -10,17 +10,77 @@ pub enum TokenType {
     CodeAddress,    // "CODEADDR" in original source
     CodeValue,      // "CODEVAL" in original source
     ARMAXCode,      // "ARMCODE" in original source
+    Comment,        // Text after a comment prefix, without the prefix
     EndOfBlock,     // "ENDCODE" in original source
     EndOfLine,      // "ENDLINE" in original source
     NewLine,
 }
 
+// Prefixes starting a comment, which runs to the end of its line
+pub const COMMENT_PREFIXES: [&str; 3] = ["#", "//", ";"];
+
+// Location of some text within the input
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Span {
+    pub line:       usize,          // Line number, counting from 1
+    pub column:     usize,          // Column of the first character, counting from 1
+    pub length:     usize,          // Length in characters
+}
+
+impl Span {
+    // Span covering both this span and a later one, if they're on the same line
+    pub fn to(self, end: Span) -> Span {
+        if end.line != self.line || end.column < self.column {
+            return self;
+        }
+        Span {
+            length: end.column + end.length - self.column,
+            ..self
+        }
+    }
+
+    // Span of a single character at an offset into this span
+    pub fn at(self, offset: usize) -> Span {
+        Span {
+            column: self.column + offset,
+            length: 1,
+            ..self
+        }
+    }
+}
+
+// Split a line of input on whitespace, keeping the span of each piece
+pub fn split_line(line: &str, number: usize) -> Vec<(&str, Span)> {
+    let mut output: Vec<(&str, Span)> = vec!();
+
+    // Byte offset and column of the piece being read, if any
+    let mut start: Option<(usize, usize)> = None;
+
+    for (column, (offset, c)) in line.char_indices().enumerate() {
+        match (start, c.is_whitespace()) {
+            (None, false) => start = Some((offset, column)),
+            (Some((begin, begin_column)), true) => {
+                output.push((&line[begin..offset], Span { line: number, column: begin_column + 1, length: column - begin_column }));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some((begin, begin_column)) = start {
+        let piece = &line[begin..];
+        output.push((piece, Span { line: number, column: begin_column + 1, length: piece.chars().count() }));
+    }
+
+    output
+}
+
 // Token parsed from input
 #[derive(Clone, Debug, PartialEq)]
 pub struct Token {
     pub string:     String,         // String of characters forming the token
     pub is_multi:   bool,           // Whether this token represents more than one type
     pub types:      Vec<TokenType>, // Type(s) of token represented
+    pub span:       Span,           // Where the token was read from
 }
 
 impl Token {
@@ -75,4 +135,36 @@ impl Token {
             }
         }
     }
-}
\ No newline at end of file
+}
+// Hex digits of a raw code word, without its 0x prefix, if it's written only in hex
+pub fn hex_digits(input: &str) -> Option<&str> {
+    let digits = input.strip_prefix("0x").or_else(|| input.strip_prefix("0X")).unwrap_or(input);
+    match !digits.is_empty() && digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        true => Some(digits),
+        false => None,
+    }
+}
+
+// Whether a token looks like it was meant as a raw code word, even if it has the wrong number of digits
+pub fn is_code_word(input: &str) -> bool {
+    hex_digits(input).is_some_and(|digits| (6..=10).contains(&digits.len()))
+}
+
+// Address and value of a raw code written as a single token, e.g. "003F38AB:0000007F" or "003F38AB0000007F"
+pub fn split_code(input: &str) -> Option<(&str, &str)> {
+    if let Some((address, value)) = input.split_once([':', '-']) {
+        return match is_code_word(address) && is_code_word(value) {
+            true => Some((address, value)),
+            false => None,
+        };
+    }
+    match hex_digits(input) {
+        Some(digits) if digits.len() == 16 && digits.len() == input.len() => Some(digits.split_at(8)),
+        _ => None,
+    }
+}
+
+// Whether a single token looks like it was meant as a whole raw code, even if it has the wrong number of digits
+pub fn is_raw_code(input: &str) -> bool {
+    split_code(input).is_some() || hex_digits(input).is_some_and(|digits| (14..=18).contains(&digits.len()))
+}
@@ -0,0 +1,30 @@
+use crate::cheat::{Cheat, CheatStates};
+use crate::error::Result;
+use crate::formats::CodeDevice;
+use crate::ir::{self, Instruction};
+
+// Translate the decrypted codes of a cheat from one device to another, through the device-independent IR
+pub fn translate_cheat(input: &Cheat, from: CodeDevice, to: CodeDevice) -> Result<Cheat> {
+    // Clone input to update and return
+    let mut output = input.clone();
+
+    if from != to {
+        let instructions = ir::decode_lines(from, &input.codes).map_err(|e| e.in_cheat(input))?;
+        let encoded = ir::encode_lines(to, &instructions).map_err(|e| e.in_cheat(input))?;
+
+        // Point every new line at the input line it came from
+        output.spans = vec!();
+        if !input.spans.is_empty() {
+            for ((line, _), codes) in instructions.iter().zip(&encoded) {
+                let span = input.spans.get(*line).copied().unwrap_or_default();
+                output.spans.extend(std::iter::repeat_n(span, codes.len() / 2));
+            }
+        }
+
+        output.codes = encoded.concat();
+        output.enable_code = instructions.iter().any(|(_, inst)| matches!(inst, Instruction::Hook { .. }));
+    }
+
+    output.state = CheatStates::Translated;
+    Ok(output)
+}